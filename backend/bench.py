@@ -0,0 +1,43 @@
+#!/usr/bin/env python3
+
+import sys
+import requests
+import datetime
+
+target = 'http://localhost:8000/api'
+repeat = int(sys.argv[1]) if len(sys.argv) > 1 else 50
+
+cases = {
+    'opening': {
+        'wordlen': 5,
+        'lang': 'en',
+        'trial': [],
+        'resp': [],
+        'k': 10,
+    },
+    'midgame': {
+        'wordlen': 5,
+        'lang': 'en',
+        'trial': ['arose', 'tires'],
+        'resp': ['02011', '00112'],
+        'k': 10,
+    },
+    'long word': {
+        'wordlen': 9,
+        'lang': 'en',
+        'trial': ['relations'],
+        'resp': ['010000100'],
+        'k': 10,
+    },
+}
+
+for name, data in cases.items():
+    elapsed = []
+    for _ in range(repeat):
+        start = datetime.datetime.now()
+        requests.post(f'{target}/pred', json=data)
+        elapsed.append((datetime.datetime.now() - start).total_seconds() * 1000.0)
+
+    elapsed.sort()
+    mean = sum(elapsed) / len(elapsed)
+    print(f'{name:>10}: mean {mean:7.2f} ms  p50 {elapsed[len(elapsed) // 2]:7.2f} ms  p95 {elapsed[int(len(elapsed) * 0.95)]:7.2f} ms')
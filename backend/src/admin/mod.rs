@@ -0,0 +1,251 @@
+use std::{collections::{BTreeMap, HashSet}, fs::{self, OpenOptions}, io::Write, path::Path, sync::Mutex};
+use lazy_static::lazy_static;
+use rocket::{Outcome, State};
+use rocket::http::Status;
+use rocket::request::{self, FromRequest, Request};
+use rocket_contrib::json::Json;
+
+use crate::{Resp, respond};
+use crate::config::{Config, DictionaryConfig, DictionarySettings, DEFAULT_DICT_DIR};
+use crate::date;
+use crate::dict::{self, CharMapper, Dictionary, DictionaryInfo, get_dict_service};
+
+lazy_static! {
+    // serializes admin changes to word lists on disk
+    static ref EDIT_LOCK: Mutex<()> = Mutex::new(());
+}
+
+// request guard for the admin api: `Authorization: Bearer <admin_token>`
+pub struct Admin;
+
+impl<'a, 'r> FromRequest<'a, 'r> for Admin {
+    type Error = String;
+
+    fn from_request(request: &'a Request<'r>) -> request::Outcome<Admin, String> {
+        let token = match request.guard::<State<Config>>() {
+            Outcome::Success(conf) => conf.admin_token.clone(),
+            _ => None
+        };
+        let token = match token {
+            Some(token) => token,
+            None => return Outcome::Failure((Status::Forbidden, "admin api is disabled".to_string()))
+        };
+
+        match request.headers().get_one("Authorization") {
+            Some(value) if value == format!("Bearer {}", token) => Outcome::Success(Admin),
+            _ => Outcome::Failure((Status::Unauthorized, "invalid admin token".to_string()))
+        }
+    }
+}
+
+#[post("/reload/<lang>")]
+pub fn reload(_admin: Admin, lang: String) -> Json<Resp<String>> {
+    match dict::reload_in_background(&lang) {
+        Ok(_) => Json(Resp {
+            success: true,
+            msg: Some(format!("reloading dictionary {}", lang)),
+            result: None
+        }),
+        Err(msg) => respond(Err(msg))
+    }
+}
+
+#[derive(Deserialize)]
+pub struct NewDictionaryReq {
+    words: Vec<String>,
+    charset: Option<String>,
+    #[serde(default)]
+    strict: bool,
+    #[serde(default)]
+    metadata: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize)]
+pub struct EditWordsReq {
+    #[serde(default)]
+    add: Vec<String>,
+    #[serde(default)]
+    remove: Vec<String>,
+}
+
+#[derive(Deserialize)]
+pub struct PastAnswerReq {
+    word: String,
+    // `YYYY-MM-DD`, today (utc) if left out
+    date: Option<String>,
+}
+
+fn dict_info(lang: &String) -> Json<Resp<DictionaryInfo>> {
+    let info = get_dict_service().read().unwrap().info(lang);
+    respond(info.ok_or(format!("dictionary {} not found", lang)))
+}
+
+fn is_valid_lang(lang: &String) -> bool {
+    !lang.is_empty() && lang.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+// write to a temporary file first so a crash never leaves a half-written word list behind
+fn write_word_list(path: &Path, lines: &Vec<String>) -> Result<(), String> {
+    let tmp_path = path.with_extension("txt.tmp");
+    let mut content = lines.join("\n");
+    content.push('\n');
+    fs::write(&tmp_path, content).map_err(|e| format!("{}: {}", tmp_path.display(), e))?;
+    fs::rename(&tmp_path, path).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn validate_words(char_mapper: &Box<dyn CharMapper>, words: &Vec<String>) -> Result<Vec<Vec<u16>>, String> {
+    let mut res: Vec<Vec<u16>> = Vec::new();
+    for word in words.iter() {
+        match char_mapper.try_map_word(word.trim()) {
+            Some(mapped) if !mapped.is_empty() => res.push(mapped),
+            _ => return Err(format!("invalid word {:?}", word))
+        }
+    }
+    Ok(res)
+}
+
+#[post("/dict/<lang>", data = "<req>")]
+pub fn create_dict(_admin: Admin, conf: State<Config>, lang: String, req: Json<NewDictionaryReq>) -> Json<Resp<DictionaryInfo>> {
+    if !is_valid_lang(&lang) {
+        return respond(Err(format!("invalid dictionary name {:?}", lang)));
+    }
+
+    let _lck = EDIT_LOCK.lock().unwrap();
+    if get_dict_service().read().unwrap().get(&lang).is_some() {
+        return respond(Err(format!("dictionary {} already exists", lang)));
+    }
+
+    let dict_dir = conf.dict_dir.clone().unwrap_or(DEFAULT_DICT_DIR.to_string());
+    let path = Path::new(&dict_dir).join(format!("{}.txt", lang));
+    if path.exists() {
+        return respond(Err(format!("{} already exists", path.display())));
+    }
+
+    let dict_conf = DictionaryConfig {
+        lang: lang.clone(),
+        path: path.to_string_lossy().to_string(),
+        charset: req.charset.clone(),
+        answers: None,
+        past_answers: None,
+        strict: req.strict,
+        metadata: req.metadata.clone(),
+    };
+
+    // the directory scan only sees the word list, so keep the rest next to it
+    let settings_path = DictionarySettings::path(&path, &lang);
+    let settings = DictionarySettings {
+        charset: req.charset.clone(),
+        strict: req.strict,
+        metadata: req.metadata.clone(),
+    };
+    if let Err(e) = settings.save(&settings_path) {
+        return respond(Err(e));
+    }
+
+    let words: Vec<String> = req.words.iter().map(|word| word.trim().to_string()).collect();
+    if let Err(e) = write_word_list(&path, &words) {
+        let _ = fs::remove_file(&settings_path);
+        return respond(Err(e));
+    }
+    let dict = match Dictionary::from_config(&dict_conf) {
+        Ok(dict) => dict,
+        Err(e) => {
+            let _ = fs::remove_file(&path);
+            let _ = fs::remove_file(&settings_path);
+            return respond(Err(e));
+        }
+    };
+
+    get_dict_service().write().unwrap().register(&dict_conf, dict);
+    dict_info(&lang)
+}
+
+#[post("/dict/<lang>/words", data = "<req>")]
+pub fn edit_words(_admin: Admin, lang: String, req: Json<EditWordsReq>) -> Json<Resp<DictionaryInfo>> {
+    let _lck = EDIT_LOCK.lock().unwrap();
+    let (dict_conf, char_mapper) = {
+        let svc = get_dict_service().read().unwrap();
+        match (svc.source(&lang), svc.get(&lang)) {
+            (Some(dict_conf), Some(dictarc)) => (dict_conf.clone(), dictarc.read().unwrap().get_char_mapper()),
+            _ => return respond(Err(format!("dictionary {} not found", lang)))
+        }
+    };
+
+    let added = match validate_words(&char_mapper, &req.add) {
+        Ok(added) => added,
+        Err(e) => return respond(Err(e))
+    };
+    let removed: HashSet<Vec<u16>> = match validate_words(&char_mapper, &req.remove) {
+        Ok(removed) => removed.into_iter().collect(),
+        Err(e) => return respond(Err(e))
+    };
+
+    let content = match fs::read_to_string(&dict_conf.path) {
+        Ok(content) => content,
+        Err(e) => return respond(Err(format!("{}: {}", dict_conf.path, e)))
+    };
+
+    let mut lines: Vec<String> = Vec::new();
+    let mut present: HashSet<Vec<u16>> = HashSet::new();
+    for line in content.lines() {
+        let mapped = char_mapper.map_word(&line.trim().to_string());
+        if !removed.contains(&mapped) {
+            present.insert(mapped);
+            lines.push(line.to_string());
+        }
+    }
+    for (word, mapped) in req.add.iter().zip(added.into_iter()) {
+        if present.insert(mapped) {
+            lines.push(word.trim().to_string());
+        }
+    }
+
+    if let Err(e) = write_word_list(Path::new(&dict_conf.path), &lines) {
+        return respond(Err(e));
+    }
+    let res = Dictionary::from_config(&dict_conf)
+        .and_then(|dict| get_dict_service().read().unwrap().replace(&lang, dict));
+    if let Err(e) = res {
+        return respond(Err(e));
+    }
+    dict_info(&lang)
+}
+
+#[post("/dict/<lang>/past", data = "<req>")]
+pub fn add_past_answer(_admin: Admin, lang: String, req: Json<PastAnswerReq>) -> Json<Resp<DictionaryInfo>> {
+    let _lck = EDIT_LOCK.lock().unwrap();
+    let (dict_conf, char_mapper) = {
+        let svc = get_dict_service().read().unwrap();
+        match (svc.source(&lang), svc.get(&lang)) {
+            (Some(dict_conf), Some(dictarc)) => (dict_conf.clone(), dictarc.read().unwrap().get_char_mapper()),
+            _ => return respond(Err(format!("dictionary {} not found", lang)))
+        }
+    };
+
+    if let Err(e) = validate_words(&char_mapper, &vec![req.word.clone()]) {
+        return respond(Err(e));
+    }
+    let days = match date::parse_date(req.date.as_ref().map_or("today", |date| date.as_str())) {
+        Ok(days) => days,
+        Err(e) => return respond(Err(e))
+    };
+
+    let path = dict_conf.past_answers_path();
+    // a hand-edited file may lack the final newline
+    let sep = match fs::read_to_string(&path) {
+        Ok(content) if !content.is_empty() && !content.ends_with('\n') => "\n",
+        _ => ""
+    };
+    let res = OpenOptions::new().create(true).append(true).open(&path)
+        .and_then(|mut file| write!(file, "{}{} {}\n", sep, date::format_date(days), req.word.trim()));
+    if let Err(e) = res {
+        return respond(Err(format!("{}: {}", path, e)));
+    }
+
+    let res = Dictionary::from_config(&dict_conf)
+        .and_then(|dict| get_dict_service().read().unwrap().replace(&lang, dict));
+    if let Err(e) = res {
+        return respond(Err(e));
+    }
+    dict_info(&lang)
+}
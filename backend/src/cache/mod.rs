@@ -0,0 +1,67 @@
+use std::{hash::Hash, sync::{Mutex, atomic::{AtomicU64, Ordering}}, time::{Duration, Instant}};
+use lru::LruCache;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub len: usize,
+    pub capacity: usize,
+}
+
+// LRU cache whose entries also expire after `ttl`. a capacity of 0 disables it.
+pub struct TtlLruCache<K, V> where K: Hash + Eq {
+    entries: Mutex<LruCache<K, (Instant, V)>>,
+    capacity: usize,
+    ttl: Option<Duration>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl<K, V> TtlLruCache<K, V> where K: Hash + Eq, V: Clone {
+    pub fn new(capacity: usize, ttl: Option<Duration>) -> TtlLruCache<K, V> {
+        TtlLruCache {
+            entries: Mutex::new(LruCache::new(capacity)),
+            capacity,
+            ttl,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<V> {
+        let mut entries = self.entries.lock().unwrap();
+        let res = match entries.get(key) {
+            Some((inserted_at, value)) => match self.ttl {
+                Some(ttl) if inserted_at.elapsed() > ttl => None,
+                _ => Some(value.clone())
+            },
+            None => None
+        };
+        if res.is_none() {
+            entries.pop(key);
+        }
+
+        match res {
+            Some(_) => self.hits.fetch_add(1, Ordering::Relaxed),
+            None => self.misses.fetch_add(1, Ordering::Relaxed)
+        };
+        res
+    }
+
+    pub fn insert(&self, key: K, value: V) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.entries.lock().unwrap().put(key, (Instant::now(), value));
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            len: self.entries.lock().unwrap().len(),
+            capacity: self.capacity,
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use std::collections::BTreeMap;
+use crate::config::{Config, DictionaryConfig};
+use crate::dict::Dictionary;
+use std::fs;
+use std::time::Instant;
+
+use crate::solver::{self, DecisionTree, OpeningBook, PredictOptions, Strategy, DEFAULT_TIME_BUDGET};
+
+const USAGE: &str = "usage: wordle-solve-backend book <lang> <wordlen> [--strategy frequency] [--k N] [--out path]
+       wordle-solve-backend tree <lang> <wordlen> [--start word] [--strategy frequency] [--json path] [--text path]
+       wordle-solve-backend bench <lang> <wordlen> [--trial arose,tires --resp 02011,00112] [--strategy frequency] [--k N] [--repeat N]";
+
+// offline commands; without arguments the server is started instead
+pub fn run(args: &Vec<String>) -> Result<(), String> {
+    match args[0].as_str() {
+        "book" => book(&args[1..]),
+        "tree" => tree(&args[1..]),
+        "bench" => bench(&args[1..]),
+        _ => Err(USAGE.to_string())
+    }
+}
+
+// splits `--name value` pairs from positional arguments
+fn parse_args(args: &[String]) -> Result<(Vec<String>, BTreeMap<String, String>), String> {
+    let mut positional: Vec<String> = Vec::new();
+    let mut options: BTreeMap<String, String> = BTreeMap::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if let Some(name) = arg.strip_prefix("--") {
+            match iter.next() {
+                Some(value) => { options.insert(name.to_string(), value.clone()); }
+                None => return Err(format!("missing value for --{}", name))
+            }
+        } else {
+            positional.push(arg.clone());
+        }
+    }
+    Ok((positional, options))
+}
+
+fn load_dictionary(lang: &String) -> Result<(DictionaryConfig, Dictionary), String> {
+    let conf = Config::load()?;
+    let dict_conf = match conf.dictionaries()?.into_iter().find(|dict_conf| &dict_conf.lang == lang) {
+        Some(dict_conf) => dict_conf,
+        None => return Err(format!("dictionary {} not found", lang))
+    };
+    let dict = Dictionary::from_config(&dict_conf)?;
+    Ok((dict_conf, dict))
+}
+
+// `<lang> <wordlen>` and --strategy, shared by the solver commands
+fn parse_target(args: &[String]) -> Result<(String, usize, Strategy, BTreeMap<String, String>), String> {
+    let (positional, options) = parse_args(args)?;
+    if positional.len() != 2 {
+        return Err(USAGE.to_string());
+    }
+    let wordlen: usize = positional[1].parse().map_err(|_| format!("invalid wordlen {}", positional[1]))?;
+    let strategy = match options.get("strategy") {
+        Some(name) => Strategy::from_name(name).ok_or(format!("unknown strategy {}", name))?,
+        None => Strategy::Frequency
+    };
+    Ok((positional[0].clone(), wordlen, strategy, options))
+}
+
+fn book(args: &[String]) -> Result<(), String> {
+    let (lang, wordlen, strategy, options) = parse_target(args)?;
+    let k: usize = match options.get("k") {
+        Some(k) => k.parse().map_err(|_| format!("invalid k {}", k))?,
+        None => 10
+    };
+
+    let (dict_conf, dict) = load_dictionary(&lang)?;
+    let book = OpeningBook::build(&dict, wordlen, strategy, k)?;
+    let path = match options.get("out") {
+        Some(path) => path.into(),
+        None => OpeningBook::path(&dict_conf, wordlen, strategy)
+    };
+    book.save(&path)?;
+    println!("{}: first guess {}, {} responses", path.display(), book.first_guess, book.second.len());
+    Ok(())
+}
+
+fn tree(args: &[String]) -> Result<(), String> {
+    let (lang, wordlen, strategy, options) = parse_target(args)?;
+
+    let (_, dict) = load_dictionary(&lang)?;
+    let tree = DecisionTree::build(&dict, wordlen, strategy, options.get("start").cloned())?;
+
+    let json_path = options.get("json").cloned().unwrap_or(format!("{}.{}.{}.tree.json", lang, wordlen, strategy.name()));
+    let content = serde_json::to_string(&tree).map_err(|e| e.to_string())?;
+    fs::write(&json_path, content).map_err(|e| format!("{}: {}", json_path, e))?;
+    let text_path = options.get("text").cloned().unwrap_or(format!("{}.{}.{}.tree.txt", lang, wordlen, strategy.name()));
+    fs::write(&text_path, tree.to_text()).map_err(|e| format!("{}: {}", text_path, e))?;
+
+    let stats = &tree.stats;
+    println!("{}, {}: first guess {}", json_path, text_path, tree.root.guess);
+    println!("{} answers, average {:.3} guesses, at most {}, {} unsolved", stats.answer_cnt, stats.average_depth, stats.max_depth, stats.unsolved);
+    for (depth, cnt) in stats.depths.iter() {
+        println!("{:>3}: {}", depth, cnt);
+    }
+    Ok(())
+}
+
+// comma separated list option, empty if not given
+fn list_option(options: &BTreeMap<String, String>, name: &str) -> Vec<String> {
+    match options.get(name) {
+        Some(value) => value.split(',').map(|item| item.trim().to_string()).filter(|item| !item.is_empty()).collect(),
+        None => Vec::new()
+    }
+}
+
+// times filter + scoring in-process, without the http and cache layers bench.py goes through
+fn bench(args: &[String]) -> Result<(), String> {
+    let (lang, wordlen, strategy, options) = parse_target(args)?;
+    let k: usize = match options.get("k") {
+        Some(k) => k.parse().map_err(|_| format!("invalid k {}", k))?,
+        None => 10
+    };
+    let repeat: usize = match options.get("repeat") {
+        Some(repeat) => repeat.parse().map_err(|_| format!("invalid repeat {}", repeat))?,
+        None => 50
+    };
+    if repeat == 0 {
+        return Err("repeat must be positive".to_string());
+    }
+    let trial = list_option(&options, "trial");
+    let resp = list_option(&options, "resp");
+
+    let (_, dict) = load_dictionary(&lang)?;
+    let opts = PredictOptions::default();
+    let mut elapsed: Vec<f64> = Vec::new();
+    let mut candidate_cnt = 0;
+    for _ in 0..repeat {
+        let start = Instant::now();
+        let word_filter = solver::history_filter(&dict, wordlen, &trial, &resp)?;
+        let res = solver::predict_with(&dict, &word_filter, wordlen, k, strategy, DEFAULT_TIME_BUDGET, &opts);
+        elapsed.push(start.elapsed().as_secs_f64() * 1000.0);
+        candidate_cnt = res.candidate_cnt;
+    }
+
+    elapsed.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let mean = elapsed.iter().sum::<f64>() / elapsed.len() as f64;
+    println!("{} candidates, {} runs: mean {:.2} ms  p50 {:.2} ms  p95 {:.2} ms", candidate_cnt, repeat, mean,
+        elapsed[elapsed.len() / 2], elapsed[elapsed.len() * 95 / 100]);
+    Ok(())
+}
@@ -0,0 +1,165 @@
+use std::{collections::BTreeMap, env, fs, path::Path};
+
+pub const DEFAULT_CONFIG_PATH: &str = "./wordle-hint.toml";
+pub const DEFAULT_DICT_DIR: &str = "./dict";
+pub const DEFAULT_CACHE_SIZE: usize = 4096;
+pub const DEFAULT_STATS_PATH: &str = "./stats.jsonl";
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Config {
+    // every `<lang>.txt` in this directory is registered unless listed explicitly
+    pub dict_dir: Option<String>,
+    #[serde(default, rename = "dictionary")]
+    pub dictionaries: Vec<DictionaryConfig>,
+    // admin endpoints are disabled unless a token is set here or in $WORDLE_HINT_ADMIN_TOKEN
+    pub admin_token: Option<String>,
+    // reload dictionaries whose files changed, checking this often
+    pub watch_interval_secs: Option<u64>,
+    // size of the scoring thread pool; defaults to one thread per core
+    pub threads: Option<usize>,
+    // number of cached /api/pred results (0 disables the cache) and how long they stay valid
+    pub cache_size: Option<usize>,
+    pub cache_ttl_secs: Option<u64>,
+    // finished games of hosted players, one json object per line
+    pub stats_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DictionaryConfig {
+    pub lang: String,
+    pub path: String,
+    pub charset: Option<String>,
+    pub answers: Option<String>,
+    // `YYYY-MM-DD word` per line; defaults to `<lang>.past.txt` next to the word list
+    pub past_answers: Option<String>,
+    #[serde(default)]
+    pub strict: bool,
+    #[serde(default)]
+    pub metadata: BTreeMap<String, String>,
+}
+
+// settings of a scanned `<lang>.txt`, kept in `<lang>.toml` next to it
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DictionarySettings {
+    pub charset: Option<String>,
+    #[serde(default)]
+    pub strict: bool,
+    #[serde(default)]
+    pub metadata: BTreeMap<String, String>,
+}
+
+impl DictionarySettings {
+    pub fn path(list_path: &Path, lang: &str) -> std::path::PathBuf {
+        list_path.with_file_name(format!("{}.toml", lang))
+    }
+
+    pub fn load(path: &Path) -> Result<DictionarySettings, String> {
+        let content = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        toml::from_str::<DictionarySettings>(&content).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let content = toml::to_string(self).map_err(|e| format!("{}: {}", path.display(), e))?;
+        fs::write(path, content).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+impl DictionaryConfig {
+    pub fn charset(&self) -> String {
+        self.charset.clone().unwrap_or_else(|| self.lang.clone())
+    }
+
+    pub fn past_answers_path(&self) -> String {
+        match &self.past_answers {
+            Some(path) => path.clone(),
+            None => Path::new(&self.path).with_file_name(format!("{}.past.txt", self.lang)).to_string_lossy().to_string()
+        }
+    }
+}
+
+impl Config {
+    // reads $WORDLE_HINT_CONFIG or ./wordle-hint.toml; without either, ./dict is scanned.
+    pub fn load() -> Result<Config, String> {
+        let path = env::var("WORDLE_HINT_CONFIG").unwrap_or(DEFAULT_CONFIG_PATH.to_string());
+        let mut conf = if Path::new(&path).exists() {
+            let content = match fs::read_to_string(&path) {
+                Ok(content) => content,
+                Err(e) => return Err(format!("{}: {}", path, e))
+            };
+            toml::from_str::<Config>(&content).map_err(|e| format!("{}: {}", path, e))?
+        } else {
+            Config {
+                dict_dir: Some(DEFAULT_DICT_DIR.to_string()),
+                ..Config::default()
+            }
+        };
+
+        if let Ok(token) = env::var("WORDLE_HINT_ADMIN_TOKEN") {
+            conf.admin_token = Some(token);
+        }
+        Ok(conf)
+    }
+
+    pub fn dictionaries(&self) -> Result<Vec<DictionaryConfig>, String> {
+        let mut res = self.dictionaries.clone();
+        if let Some(dict_dir) = &self.dict_dir {
+            for found in scan_dict_dir(dict_dir)? {
+                if res.iter().all(|e| e.lang != found.lang) {
+                    res.push(found);
+                }
+            }
+        }
+        Ok(res)
+    }
+}
+
+// `<lang>.txt` is a word list, `<lang>.answers.txt` its answer list and `<lang>.toml` its settings if present.
+fn scan_dict_dir(dict_dir: &String) -> Result<Vec<DictionaryConfig>, String> {
+    let entries = match fs::read_dir(dict_dir) {
+        Ok(entries) => entries,
+        Err(e) => return Err(format!("{}: {}", dict_dir, e))
+    };
+
+    let mut res: Vec<DictionaryConfig> = Vec::new();
+    for entry in entries {
+        let path = match entry {
+            Ok(entry) => entry.path(),
+            Err(e) => return Err(format!("{}: {}", dict_dir, e))
+        };
+        if path.extension().map_or(true, |ext| ext != "txt") {
+            continue;
+        }
+        let lang = match path.file_stem().and_then(|stem| stem.to_str()) {
+            Some(stem) if !stem.contains('.') => stem.to_string(),
+            _ => continue
+        };
+
+        let settings_path = DictionarySettings::path(&path, &lang);
+        let settings = if settings_path.exists() {
+            match DictionarySettings::load(&settings_path) {
+                Ok(settings) => settings,
+                Err(e) => {
+                    // loading it with the wrong charset would be worse than not loading it
+                    eprintln!("skipping dictionary {}: {}", lang, e);
+                    continue;
+                }
+            }
+        } else {
+            DictionarySettings::default()
+        };
+
+        let answers = path.with_file_name(format!("{}.answers.txt", lang));
+        res.push(DictionaryConfig {
+            lang,
+            path: path.to_string_lossy().to_string(),
+            charset: settings.charset,
+            answers: if answers.exists() { Some(answers.to_string_lossy().to_string()) } else { None },
+            past_answers: None,
+            strict: settings.strict,
+            metadata: settings.metadata,
+        });
+    }
+
+    res.sort_by(|a, b| a.lang.cmp(&b.lang));
+    Ok(res)
+}
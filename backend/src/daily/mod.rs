@@ -0,0 +1,70 @@
+use rocket_contrib::json::Json;
+
+use crate::{Resp, respond};
+use crate::date::{days_from_civil, format_date, parse_date};
+use crate::dict::{Dictionary, get_dictionary};
+use crate::game::{answer_pool, splitmix64};
+
+// puzzle 0 is the one for this date
+pub const DAILY_EPOCH: (i64, i64, i64) = (2022, 1, 1);
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DailyPuzzle {
+    pub id: String,
+    pub lang: String,
+    pub wordlen: usize,
+    pub date: String,
+    pub number: u64,
+}
+
+pub fn puzzle(lang: &String, wordlen: usize, date: &str) -> Result<DailyPuzzle, String> {
+    let days = parse_date(date)?;
+    let epoch = days_from_civil(DAILY_EPOCH.0, DAILY_EPOCH.1, DAILY_EPOCH.2);
+    if days < epoch {
+        return Err(format!("there is no puzzle before {}", format_date(epoch)));
+    }
+    let number = (days - epoch) as u64;
+    Ok(DailyPuzzle {
+        id: format!("{}-{}-{}", lang, wordlen, number),
+        lang: lang.clone(),
+        wordlen,
+        date: format_date(days),
+        number,
+    })
+}
+
+// every answer comes up once before any repeats: each cycle through the answer list
+// is its own permutation, seeded by the language, the word length and the cycle
+pub fn answer(dict: &Dictionary, puzzle: &DailyPuzzle) -> Result<String, String> {
+    let pool = answer_pool(dict, puzzle.wordlen);
+    if pool.is_empty() {
+        return Err(format!("no words of length {}", puzzle.wordlen));
+    }
+    let cycle = puzzle.number / pool.len() as u64;
+
+    let mut seed: u64 = 0xcbf29ce484222325;
+    let key = format!("{}/{}/{}", puzzle.lang, puzzle.wordlen, cycle);
+    for b in key.bytes() {
+        seed ^= b as u64;
+        seed = seed.wrapping_mul(0x100000001b3);
+    }
+
+    let mut order: Vec<usize> = (0..pool.len()).collect();
+    for i in (1..order.len()).rev() {
+        let j = (splitmix64(&mut seed) % (i as u64 + 1)) as usize;
+        order.swap(i, j);
+    }
+    Ok(dict.char_mapper.unmap_word(&pool[order[(puzzle.number % pool.len() as u64) as usize]]))
+}
+
+// the puzzle id for a date (today by default), never the word itself
+#[get("/daily/<lang>/<wordlen>?<date>")]
+pub fn daily(lang: String, wordlen: usize, date: Option<String>) -> Json<Resp<DailyPuzzle>> {
+    let res = get_dictionary(&lang).and_then(|dict| {
+        let puzzle = puzzle(&lang, wordlen, date.as_ref().map_or("today", |date| date.as_str()))?;
+        // make sure there is a word to play
+        answer(&dict, &puzzle)?;
+        Ok(puzzle)
+    });
+    respond(res)
+}
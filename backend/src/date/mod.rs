@@ -0,0 +1,75 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// days since 1970-01-01 in the proleptic gregorian calendar
+pub fn days_from_civil(y: i64, m: i64, d: i64) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = y - era * 400;
+    let mp = (m + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+pub fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719468;
+    let era = if z >= 0 { z } else { z - 146096 } / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    (if m <= 2 { yoe + era * 400 + 1 } else { yoe + era * 400 }, m, d)
+}
+
+pub fn format_date(days: i64) -> String {
+    let (y, m, d) = civil_from_days(days);
+    format!("{:04}-{:02}-{:02}", y, m, d)
+}
+
+// `YYYY-MM-DD` or `today` (utc) to days since 1970-01-01
+pub fn parse_date(date: &str) -> Result<i64, String> {
+    if date == "today" {
+        let secs = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        return Ok((secs / 86400) as i64);
+    }
+
+    let parts: Vec<i64> = date.split('-').map(|part| part.parse::<i64>()).collect::<Result<_, _>>()
+        .map_err(|_| format!("invalid date {}", date))?;
+    // the range keeps the arithmetic below from overflowing
+    if parts.len() != 3 || !(1..=9999).contains(&parts[0]) || !(1..=12).contains(&parts[1]) || !(1..=31).contains(&parts[2]) {
+        return Err(format!("invalid date {}", date));
+    }
+    let days = days_from_civil(parts[0], parts[1], parts[2]);
+    // rejects e.g. 2022-02-30
+    if civil_from_days(days) != (parts[0], parts[1], parts[2]) {
+        return Err(format!("invalid date {}", date));
+    }
+    Ok(days)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates_round_trip() {
+        let first = days_from_civil(1, 1, 1);
+        let last = days_from_civil(9999, 12, 31);
+        for days in (first..=last).step_by(97).chain(first..first + 800).chain(last - 800..=last) {
+            assert_eq!(parse_date(&format_date(days)), Ok(days), "{}", format_date(days));
+        }
+        assert_eq!(parse_date("1970-01-01"), Ok(0));
+        assert_eq!(parse_date("2024-02-29"), Ok(days_from_civil(2024, 2, 29)));
+    }
+
+    #[test]
+    fn out_of_range_dates_are_rejected() {
+        for date in ["9223372036854775807-01-01", "2022-9223372036854775807-01", "2022-01-9223372036854775807",
+            "-9223372036854775808-01-01", "0000-01-01", "10000-01-01", "2022-00-10", "2022-13-01", "2022-01-00",
+            "2022-01-32", "2022-02-29", "2100-02-29", "2022-1", "2022-01-01-01", "", "yesterday"].iter() {
+            assert!(parse_date(date).is_err(), "{}", date);
+        }
+    }
+}
@@ -1,32 +1,72 @@
-use std::{cmp::max, path::Path, fs::File, io::{self, BufRead}, collections::{BinaryHeap, HashMap}, sync::{Arc, RwLock}};
+use std::{cmp::max, path::Path, fs::{self, File}, io::{self, BufRead}, collections::{BTreeMap, BinaryHeap, HashMap, HashSet}, sync::{Arc, RwLock, atomic::{AtomicU64, Ordering}}, thread, time::{Duration, SystemTime}};
 use lazy_static::lazy_static;
 
+use crate::config::DictionaryConfig;
+
 use ordered_float::NotNan;
+use rayon::prelude::*;
+
+// below this many words a parallel split costs more than it saves
+const PAR_MIN_LEN: usize = 1024;
 
 pub trait CharMapper: CharMapperClone + Send + Sync {
     fn map_char(&self, c:char)->u16;
     fn unmap_char(&self, n:u16)->char;
 
+    // maps the symbol at the start of `s` and returns it with its length in bytes.
+    // mappers whose tiles span several characters (e.g. welsh "ll") override this.
+    fn map_symbol(&self, s:&str)->(u16, usize) {
+        match s.chars().next() {
+            Some(c) => (self.map_char(c), c.len_utf8()),
+            None => (0, 0)
+        }
+    }
+
+    fn unmap_symbol(&self, n:u16)->String {
+        let ch = self.unmap_char(n);
+        if ch == ' ' {
+            return String::new();
+        }
+        ch.to_string()
+    }
+
+    // splits `s` into tiles. unmappable pieces are kept with symbol 0.
+    fn tokenize<'a>(&self, s:&'a str)->Vec<(u16, &'a str)> {
+        let mut res: Vec<(u16, &'a str)> = Vec::new();
+        let mut rest = s;
+        while !rest.is_empty() {
+            let (v, mut len) = self.map_symbol(rest);
+            if len == 0 {
+                len = rest.chars().next().unwrap().len_utf8();
+            }
+            res.push((v, &rest[..len]));
+            rest = &rest[len..];
+        }
+        res
+    }
+
+    // the symbols of `s`, or None if any part of it is not in the alphabet
+    fn try_map_word(&self, s:&str)->Option<Vec<u16>> {
+        self.tokenize(s).into_iter().map(|(v, _)| if v > 0 { Some(v) } else { None }).collect()
+    }
+
+    // drops what it cannot map; use try_map_word for input that has to be a word
     fn map_word(&self, s:&String)->Vec<u16> {
         let mut res: Vec<u16> = Vec::new();
-        for c in s.chars() {
-            let v = self.map_char(c);
+        for (v, _) in self.tokenize(s) {
             if v > 0 {
-                res.push(self.map_char(c));
+                res.push(v);
             }
         }
         return res;
     }
 
     fn unmap_word(&self, v:&Vec<u16>)->String {
-        let mut res: Vec<char> = Vec::new();
+        let mut res = String::new();
         for val in v {
-            let ch = self.unmap_char(*val);
-            if ch != ' ' {
-                res.push(ch);
-            }
+            res.push_str(&self.unmap_symbol(*val));
         }
-        return res.into_iter().collect();
+        return res;
     }
 
     fn alphabet_cnt(&self) -> u16;
@@ -51,36 +91,309 @@ impl Clone for Box<dyn CharMapper> {
 
 
 
+#[derive(Debug, Clone, Serialize)]
+pub struct PastAnswer {
+    pub date: String,
+    pub word: String,
+}
+
+// what to do with past answers when looking for the answer
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum PastAnswerMode {
+    Keep,
+    Exclude,
+    Downweight,
+}
+
+impl Default for PastAnswerMode {
+    fn default() -> PastAnswerMode {
+        PastAnswerMode::Keep
+    }
+}
+
+// added to the score of a down-weighted past answer: scores are negative log likelihoods,
+// so this ranks it as if it were ten times less likely
+pub const PAST_ANSWER_PENALTY: f32 = std::f32::consts::LN_10;
+
 pub struct Dictionary{
     pub name: String,
-    pub words: Vec<Vec<u16>>,
+    pub buckets: BTreeMap<usize, WordBucket>,
     pub char_mapper: Box<dyn CharMapper>,
+    pub load_report: Option<LoadReport>,
+    pub answers: Vec<Vec<u16>>,
+    // answers the game already used, oldest first
+    pub past_answers: Vec<PastAnswer>,
+    pub past_answer_set: HashSet<Vec<u16>>,
+    pub loaded_at: SystemTime,
+    // distinct for every loaded dictionary, so caches can tell a reloaded one apart
+    pub generation: u64,
+}
+
+static NEXT_GENERATION: AtomicU64 = AtomicU64::new(1);
+
+// words of a single length, stored back to back. each word also gets `wordlen` count masks:
+// bit c of mask i is set when symbol c+1 occurs more than i times in the word.
+#[derive(Debug, Clone)]
+pub struct WordBucket {
+    pub wordlen: usize,
+    pub symbols: Vec<u16>,
+    pub signatures: Vec<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LoadIssue {
+    pub line: usize,
+    pub text: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LoadReport {
+    pub line_cnt: usize,
+    pub word_cnt: usize,
+    pub skipped: Vec<LoadIssue>,
+    pub modified: Vec<LoadIssue>,
+    pub duplicates: Vec<LoadIssue>,
+    pub length_histogram: BTreeMap<usize, usize>,
+    // lines of the past answers file that were left out
+    pub past_answers_skipped: Vec<LoadIssue>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DictionaryInfo {
+    pub lang: String,
+    pub charset: String,
+    pub alphabet_cnt: u16,
+    pub word_cnt: usize,
+    pub word_cnt_per_len: BTreeMap<usize, usize>,
+    pub answer_cnt: usize,
+    pub past_answer_cnt: usize,
+    pub loaded_at: u64,
+    pub metadata: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DictionaryDetail {
+    #[serde(flatten)]
+    pub info: DictionaryInfo,
+    pub alphabet: Vec<String>,
+    pub path: String,
+    pub answers_path: Option<String>,
+    pub past_answers_path: String,
+    pub skipped_cnt: usize,
+    pub modified_cnt: usize,
+    pub duplicate_cnt: usize,
+    pub past_answers_skipped_cnt: usize,
+}
+
+impl CompiledFilter {
+    fn check_levels(levels: &Vec<(usize, u64, u64)>, signature: &[u64]) -> bool {
+        levels.iter().all(|(level, need, forbid)| {
+            let mask = signature[*level];
+            mask & need == *need && mask & forbid == 0
+        })
+    }
+
+    pub fn can_be_answer(&self, word: &[u16], signature: &[u64]) -> bool {
+        if self.no_answer || word.len() != self.wordlen {
+            return false;
+        }
+        for (pos, c) in word.iter().enumerate() {
+            if self.allowed[pos] & (1u64 << (c - 1)) == 0 {
+                return false;
+            }
+        }
+        CompiledFilter::check_levels(&self.answer_levels, signature)
+    }
+
+    pub fn is_explorable(&self, word: &[u16], signature: &[u64]) -> bool {
+        if self.no_explore || word.len() != self.wordlen {
+            return false;
+        }
+        CompiledFilter::check_levels(&self.explore_levels, signature)
+    }
+}
+
+impl WordBucket {
+    pub fn new(wordlen: usize) -> WordBucket {
+        assert!(wordlen > 0);
+        WordBucket { wordlen, symbols: Vec::new(), signatures: Vec::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.symbols.len() / self.wordlen
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    pub fn word(&self, idx: usize) -> &[u16] {
+        &self.symbols[idx * self.wordlen..(idx + 1) * self.wordlen]
+    }
+
+    pub fn signature(&self, idx: usize) -> &[u64] {
+        &self.signatures[idx * self.wordlen..(idx + 1) * self.wordlen]
+    }
+
+    pub fn iter(&self) -> std::slice::ChunksExact<'_, u16> {
+        self.symbols.chunks_exact(self.wordlen)
+    }
+
+    // FNV-1a over the stored words, to tell whether a precomputed file still matches
+    pub fn checksum(&self) -> u64 {
+        let mut hash: u64 = 0xcbf29ce484222325;
+        for c in self.symbols.iter() {
+            for byte in c.to_le_bytes().iter() {
+                hash ^= u64::from(*byte);
+                hash = hash.wrapping_mul(0x100000001b3);
+            }
+        }
+        hash
+    }
+
+    pub fn push(&mut self, word: &[u16]) {
+        assert!(word.len() == self.wordlen);
+        let mut signature: Vec<u64> = vec![0; self.wordlen];
+        let mut chcnt = [0usize; MAX_ALPHABET];
+        for c in word {
+            let cidx = usize::from(*c - 1);
+            signature[chcnt[cidx]] |= 1 << cidx;
+            chcnt[cidx] += 1;
+        }
+        self.symbols.extend_from_slice(word);
+        self.signatures.extend_from_slice(&signature);
+    }
+}
+
+impl LoadReport {
+    pub fn is_clean(&self) -> bool {
+        self.skipped.is_empty() && self.modified.is_empty() && self.duplicates.is_empty()
+    }
 }
 
 #[derive(Clone, Copy)]
 pub struct EnglishCharMapper {}
 
+// upper bound on the alphabet size of any mapper; WordFilter is instantiated with it.
+pub const MAX_ALPHABET: usize = 64;
+
+#[derive(Clone)]
+pub struct AlphabetCharMapper {
+    symbols: Arc<Vec<String>>,
+    spellings: Arc<HashMap<String, u16>>,
+    max_spelling_len: usize,
+}
+
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum CharConstraint {
     ShouldNotContain,
     ShouldContainAtLeast(u8),
     ShouldContainExactly(u8)
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum WordleResp {
     Black,
     Yellow,
     Green
 }
 
+// the tile colors wordle shows for `guess` when the answer is `answer`
+pub fn wordle_response(guess: &[u16], answer: &[u16]) -> Vec<WordleResp> {
+    let mut res = vec![WordleResp::Black; guess.len()];
+    let mut unmatched = [0u8; MAX_ALPHABET + 1];
+    for (pos, c) in guess.iter().enumerate() {
+        if answer[pos] == *c {
+            res[pos] = WordleResp::Green;
+        } else {
+            unmatched[usize::from(answer[pos])] += 1;
+        }
+    }
+    for (pos, c) in guess.iter().enumerate() {
+        if res[pos] != WordleResp::Green && unmatched[usize::from(*c)] > 0 {
+            unmatched[usize::from(*c)] -= 1;
+            res[pos] = WordleResp::Yellow;
+        }
+    }
+    res
+}
+
+// "0" black, "1" yellow, "2" green, as used by /api/pred
+// wordle_response as a base-3 number, position i adding 3^i times 0 (black), 1 (yellow) or 2 (green).
+// cheaper to group by than the Vec; distinct for words of up to 40 letters
+pub fn wordle_response_code(guess: &[u16], answer: &[u16]) -> u64 {
+    let mut unmatched = [0u8; MAX_ALPHABET + 1];
+    for (pos, c) in guess.iter().enumerate() {
+        if answer[pos] != *c {
+            unmatched[usize::from(answer[pos])] += 1;
+        }
+    }
+    let mut code: u64 = 0;
+    let mut place: u64 = 1;
+    for (pos, c) in guess.iter().enumerate() {
+        if answer[pos] == *c {
+            code = code.wrapping_add(2 * place);
+        } else if unmatched[usize::from(*c)] > 0 {
+            unmatched[usize::from(*c)] -= 1;
+            code = code.wrapping_add(place);
+        }
+        place = place.wrapping_mul(3);
+    }
+    code
+}
+
+// the code of an all green response
+pub fn solved_response_code(wordlen: usize) -> u64 {
+    (0..wordlen).fold(0u64, |code, _| code.wrapping_mul(3).wrapping_add(2))
+}
+
+pub fn format_response_code(code: u64, wordlen: usize) -> String {
+    let mut res = String::new();
+    let mut rest = code;
+    for _ in 0..wordlen {
+        res.push(char::from(b'0' + (rest % 3) as u8));
+        rest /= 3;
+    }
+    res
+}
+
+pub fn parse_wordle_resp(s: &str) -> Vec<WordleResp> {
+    s.chars().map(|c| match c {
+        '1' => WordleResp::Yellow,
+        '2' => WordleResp::Green,
+        _ => WordleResp::Black,
+    }).collect()
+}
+
+pub fn format_wordle_resp(resp: &[WordleResp]) -> String {
+    resp.iter().map(|r| match r {
+        WordleResp::Black => '0',
+        WordleResp::Yellow => '1',
+        WordleResp::Green => '2',
+    }).collect()
+}
+
 pub enum FilterMode {
     ForExploration,
     ForExploit
 }
 
+// a WordFilter lowered to bitmasks over symbols, checked against WordBucket signatures
 #[derive(Debug, Clone)]
+pub struct CompiledFilter {
+    wordlen: usize,
+    allowed: Vec<u64>,
+    answer_levels: Vec<(usize, u64, u64)>,
+    explore_levels: Vec<(usize, u64, u64)>,
+    // set by counts no word of this length can reach
+    no_answer: bool,
+    no_explore: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct WordFilter<const CNTALPHA:usize> {
     wordlen: u16,
     cnt_constraint: [CharConstraint; CNTALPHA],
@@ -187,7 +500,11 @@ impl<const CNTALPHA: usize> WordFilter <CNTALPHA> {
         })
     }
 
-    pub fn can_be_answer(&self, word: &Vec<u16>)-> bool {
+    pub fn wordlen(&self) -> u16 {
+        self.wordlen
+    }
+
+    pub fn can_be_answer(&self, word: &[u16])-> bool {
         if usize::from(self.wordlen) != word.len() {
             return false;
         }
@@ -230,7 +547,7 @@ impl<const CNTALPHA: usize> WordFilter <CNTALPHA> {
         return true;
     }
 
-    pub fn is_explorable(&self, word: &Vec<u16>) -> bool {
+    pub fn is_explorable(&self, word: &[u16]) -> bool {
         if usize::from(self.wordlen) != word.len() {
             return false;
         }
@@ -259,6 +576,75 @@ impl<const CNTALPHA: usize> WordFilter <CNTALPHA> {
         return true;
     }
 
+    pub fn compile(&self) -> CompiledFilter {
+        assert!(CNTALPHA <= MAX_ALPHABET);
+        let wordlen = usize::from(self.wordlen);
+
+        let mut allowed: Vec<u64> = Vec::new();
+        for pos in 0..wordlen {
+            let mut mask = if self.match_chars[pos] != 0 { 1u64 << (self.match_chars[pos] - 1) } else { !0u64 };
+            for (ch, prohibited) in self.prohib_chars[pos].iter().enumerate() {
+                if *prohibited {
+                    mask &= !(1u64 << ch);
+                }
+            }
+            allowed.push(mask);
+        }
+
+        // level i: (i, symbols that must occur more than i times, symbols that must not)
+        let mut answer_need: Vec<u64> = vec![0; wordlen + 1];
+        let mut answer_forbid: Vec<u64> = vec![0; wordlen + 1];
+        let mut explore_need: Vec<u64> = vec![0; wordlen + 1];
+        let mut explore_forbid: Vec<u64> = vec![0; wordlen + 1];
+        let mut no_answer = false;
+        let mut no_explore = false;
+        for (ch, constraint) in self.cnt_constraint.iter().enumerate() {
+            let bit = 1u64 << ch;
+            match *constraint {
+                CharConstraint::ShouldNotContain => {
+                    answer_forbid[0] |= bit;
+                    explore_forbid[0] |= bit;
+                }
+                CharConstraint::ShouldContainAtLeast(0) => {}
+                CharConstraint::ShouldContainAtLeast(cnt) => {
+                    let level = usize::from(cnt) - 1;
+                    if level >= wordlen {
+                        no_answer = true;
+                        no_explore = true;
+                    } else {
+                        answer_need[level] |= bit;
+                        explore_need[level] |= bit;
+                    }
+                }
+                CharConstraint::ShouldContainExactly(cnt) => {
+                    let level = usize::from(cnt);
+                    // exact counts only restrict answers
+                    if level > wordlen {
+                        no_answer = true;
+                    } else {
+                        if level > 0 {
+                            answer_need[level - 1] |= bit;
+                        }
+                        answer_forbid[level] |= bit;
+                    }
+                }
+            }
+        }
+
+        let levels = |need: &Vec<u64>, forbid: &Vec<u64>| -> Vec<(usize, u64, u64)> {
+            (0..wordlen).filter(|i| need[*i] != 0 || forbid[*i] != 0).map(|i| (i, need[i], forbid[i])).collect()
+        };
+
+        CompiledFilter {
+            wordlen,
+            allowed,
+            answer_levels: levels(&answer_need, &answer_forbid),
+            explore_levels: levels(&explore_need, &explore_forbid),
+            no_answer,
+            no_explore,
+        }
+    }
+
     pub fn is_char_explored(&self) -> Vec<bool> {
         let mut res:Vec<bool> = Vec::new();
         res.resize(CNTALPHA, false);
@@ -307,18 +693,132 @@ impl CharMapper for EnglishCharMapper {
 }
 
 
+// alphabet definition file, one directive per line ('#' starts a comment):
+//   ll Ll LL      a tile: its canonical spelling followed by case variants
+//   fold á a      extra spellings folded onto the tile spelled `a`
+impl AlphabetCharMapper {
+    pub fn from_file<P>(path: P) -> Result<AlphabetCharMapper, String>
+        where P: AsRef<Path> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) => return Err(format!("file open error: {}", e))
+        };
+
+        let mut symbols: Vec<String> = Vec::new();
+        let mut spellings: HashMap<String, u16> = HashMap::new();
+        let mut folds: Vec<(usize, String, String)> = Vec::new();
+
+        for (lineidx, line) in io::BufReader::new(file).lines().enumerate() {
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => return Err(format!("line {}: read error: {}", lineidx + 1, e))
+            };
+            let content = line.split('#').next().unwrap().trim();
+            let fields: Vec<&str> = content.split_whitespace().collect();
+            if fields.is_empty() {
+                continue;
+            }
+
+            if fields[0] == "fold" {
+                if fields.len() != 3 {
+                    return Err(format!("line {}: expected `fold <spelling> <tile>`", lineidx + 1));
+                }
+                folds.push((lineidx + 1, fields[1].to_string(), fields[2].to_string()));
+                continue;
+            }
+
+            symbols.push(fields[0].to_string());
+            if symbols.len() > MAX_ALPHABET {
+                return Err(format!("line {}: more than {} tiles", lineidx + 1, MAX_ALPHABET));
+            }
+            for spelling in fields.iter() {
+                if spellings.insert(spelling.to_string(), symbols.len() as u16).is_some() {
+                    return Err(format!("line {}: `{}` is defined twice", lineidx + 1, spelling));
+                }
+            }
+        }
+
+        for (lineno, spelling, target) in folds.into_iter() {
+            let symbol = match spellings.get(&target) {
+                Some(symbol) => *symbol,
+                None => return Err(format!("line {}: unknown tile `{}`", lineno, target))
+            };
+            if spellings.insert(spelling.clone(), symbol).is_some() {
+                return Err(format!("line {}: `{}` is defined twice", lineno, spelling));
+            }
+        }
+
+        if symbols.is_empty() {
+            return Err("alphabet has no tiles".to_string());
+        }
+
+        let max_spelling_len = spellings.keys().map(|k| k.chars().count()).max().unwrap();
+        Ok(AlphabetCharMapper {
+            symbols: Arc::new(symbols),
+            spellings: Arc::new(spellings),
+            max_spelling_len,
+        })
+    }
+}
+
+impl CharMapper for AlphabetCharMapper {
+    fn map_char(&self, c:char)->u16 {
+        *self.spellings.get(&c.to_string()).unwrap_or(&0)
+    }
+
+    fn unmap_char(&self, n:u16)->char {
+        if n == 0 || usize::from(n) > self.symbols.len() {
+            return ' ';
+        }
+        self.symbols[usize::from(n) - 1].chars().next().unwrap()
+    }
+
+    fn map_symbol(&self, s:&str)->(u16, usize) {
+        let ends: Vec<usize> = s.char_indices().map(|(i, c)| i + c.len_utf8()).take(self.max_spelling_len).collect();
+        for end in ends.into_iter().rev() {
+            if let Some(symbol) = self.spellings.get(&s[..end]) {
+                return (*symbol, end);
+            }
+        }
+        (0, s.chars().next().map_or(0, |c| c.len_utf8()))
+    }
+
+    fn unmap_symbol(&self, n:u16)->String {
+        if n == 0 || usize::from(n) > self.symbols.len() {
+            return String::new();
+        }
+        self.symbols[usize::from(n) - 1].clone()
+    }
+
+    fn alphabet_cnt(&self)->u16 {
+        self.symbols.len() as u16
+    }
+}
+
+
 impl Dictionary {
     pub fn new(name: &String, char_mapper: Box<dyn CharMapper>) -> Dictionary {
         Dictionary { 
             name: name.clone(),
-            words: Vec::new(),
+            buckets: BTreeMap::new(),
             char_mapper: char_mapper,
+            load_report: None,
+            answers: Vec::new(),
+            past_answers: Vec::new(),
+            past_answer_set: HashSet::new(),
+            loaded_at: SystemTime::now(),
+            generation: NEXT_GENERATION.fetch_add(1, Ordering::Relaxed),
         }
     }
 
-    pub fn from_file<P>(name: &String, path: P, char_mapper: Box<dyn CharMapper>) -> Result<Dictionary, String> 
+    // lines that are empty, unreadable or contain unmappable characters are skipped, and
+    // repeated words are kept once; all of it is recorded in the load report.
+    // in strict mode any such line rejects the whole file.
+    pub fn from_file<P>(name: &String, path: P, char_mapper: Box<dyn CharMapper>, strict: bool) -> Result<Dictionary, String> 
         where P: AsRef<Path> {
-        let mut words: Vec<Vec<u16>> = Vec::new(); 
+        let mut buckets: BTreeMap<usize, WordBucket> = BTreeMap::new();
+        let mut report = LoadReport::default();
+        let mut first_seen: HashMap<Vec<u16>, usize> = HashMap::new();
 
         let file = File::open(path);
         if let Err(e) = file {
@@ -327,29 +827,227 @@ impl Dictionary {
 
         if let Ok(file) = file {
             let lines = io::BufReader::new(file).lines();
-            for line in lines {
-                if let Ok(word) = line {
-                    let trimmed = word.trim();
-                    let mapped_word = char_mapper.map_word(&trimmed.to_string());
-                    words.push(mapped_word);
+            for (lineidx, line) in lines.enumerate() {
+                let lineno = lineidx + 1;
+                report.line_cnt = lineno;
+
+                let word = match line {
+                    Ok(word) => word,
+                    Err(e) => {
+                        report.skipped.push(LoadIssue { line: lineno, text: String::new(), reason: format!("read error: {}", e) });
+                        if e.kind() == io::ErrorKind::InvalidData {
+                            continue;
+                        }
+                        break;
+                    }
+                };
+
+                let trimmed = word.trim();
+                if trimmed.is_empty() {
+                    report.skipped.push(LoadIssue { line: lineno, text: word.clone(), reason: "empty line".to_string() });
+                    continue;
+                }
+
+                let unmappable: Vec<&str> = char_mapper.tokenize(trimmed).into_iter()
+                    .filter(|(v, _)| *v == 0)
+                    .map(|(_, piece)| piece)
+                    .collect();
+                if !unmappable.is_empty() {
+                    report.skipped.push(LoadIssue { line: lineno, text: word.clone(), reason: format!("unmappable characters {:?}", unmappable) });
+                    continue;
+                }
+
+                let mapped_word = char_mapper.map_word(&trimmed.to_string());
+                if let Some(prev) = first_seen.get(&mapped_word) {
+                    report.duplicates.push(LoadIssue { line: lineno, text: word.clone(), reason: format!("duplicate of line {}", prev) });
+                    continue;
                 }
+
+                let stored = char_mapper.unmap_word(&mapped_word);
+                if stored != word {
+                    report.modified.push(LoadIssue { line: lineno, text: word.clone(), reason: format!("stored as {:?}", stored) });
+                }
+
+                *report.length_histogram.entry(mapped_word.len()).or_insert(0) += 1;
+                buckets.entry(mapped_word.len()).or_insert_with(|| WordBucket::new(mapped_word.len())).push(&mapped_word);
+                first_seen.insert(mapped_word, lineno);
             }
         }
 
+        report.word_cnt = buckets.values().map(|bucket| bucket.len()).sum();
+        if strict && !report.is_clean() {
+            return Err(format!("rejected in strict mode: {} skipped, {} modified, {} duplicate lines",
+                report.skipped.len(), report.modified.len(), report.duplicates.len()));
+        }
+
         Ok(Dictionary {
             name: name.clone(),
-            words: words,
+            buckets: buckets,
             char_mapper: char_mapper,
+            load_report: Some(report),
+            answers: Vec::new(),
+            past_answers: Vec::new(),
+            past_answer_set: HashSet::new(),
+            loaded_at: SystemTime::now(),
+            generation: NEXT_GENERATION.fetch_add(1, Ordering::Relaxed),
         })
     }
 
+    pub fn from_config(conf: &DictionaryConfig) -> Result<Dictionary, String> {
+        let charset = conf.charset();
+        let alphabet_path = Path::new(&conf.path).with_file_name(format!("{}.alphabet", charset));
+        let char_mapper: Box<dyn CharMapper + Sync + Send> = if alphabet_path.exists() {
+            Box::new(AlphabetCharMapper::from_file(&alphabet_path)?)
+        } else {
+            match charset.as_str() {
+                "en" => Box::new(EnglishCharMapper{}),
+                _ => return Err(format!("unknown charset {}: {} not found", charset, alphabet_path.display()))
+            }
+        };
+
+        let mut dict = Dictionary::from_file(&conf.lang, &conf.path, char_mapper, conf.strict)?;
+        if let Some(answers_path) = &conf.answers {
+            let answers = Dictionary::from_file(&conf.lang, answers_path, dict.char_mapper.clone(), conf.strict)
+                .map_err(|e| format!("{}: {}", answers_path, e))?;
+            dict.answers = answers.words().map(|word| word.to_vec()).collect();
+        }
+
+        let past_path = conf.past_answers_path();
+        if Path::new(&past_path).exists() {
+            let (past_answers, skipped) = read_past_answers(&past_path, &dict.char_mapper)?;
+            dict.past_answers = past_answers;
+            if let Some(report) = dict.load_report.as_mut() {
+                report.past_answers_skipped = skipped;
+            }
+            dict.past_answer_set = dict.past_answers.iter().map(|past| dict.char_mapper.map_word(&past.word)).collect();
+        }
+        Ok(dict)
+    }
+
+    pub fn word_cnt(&self) -> usize {
+        self.buckets.values().map(|bucket| bucket.len()).sum()
+    }
+
+    // all words, shortest first
+    pub fn words(&self) -> impl Iterator<Item = &[u16]> {
+        self.buckets.values().flat_map(|bucket| bucket.iter())
+    }
+
+    // every word of the given length
+    pub fn view(&self, wordlen: usize) -> DictionaryView<'_> {
+        let bucket = self.buckets.get(&wordlen);
+        DictionaryView {
+            dict: self,
+            wordlen,
+            indices: (0..bucket.map_or(0, |bucket| bucket.len()) as u32).collect(),
+            bucket,
+        }
+    }
+
+    // only the bucket of the filter's word length can match
+    pub fn apply_filter<const CNTALPHA:usize>(&self, word_filter: &WordFilter<CNTALPHA>, mode: FilterMode) -> DictionaryView<'_> {
+        self.view(usize::from(word_filter.wordlen())).apply_filter(word_filter, mode)
+    }
+
+    pub fn get_word(&self, pos: usize)->Option<String> {
+        let word = self.words().nth(pos)?;
+        return Some(self.char_mapper.unmap_word(&word.to_vec()));
+    }
+
+    pub fn get_char_mapper(&self)->Box<dyn CharMapper> {
+        self.char_mapper.clone_box()
+    }
+}
+
+// words of one length in a dictionary, as indices into its bucket
+#[derive(Clone)]
+pub struct DictionaryView<'a> {
+    pub dict: &'a Dictionary,
+    pub wordlen: usize,
+    pub indices: Vec<u32>,
+    bucket: Option<&'a WordBucket>,
+}
+
+impl<'a> DictionaryView<'a> {
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    fn bucket(&self) -> &'a WordBucket {
+        // a non-empty view always has a bucket behind it
+        self.bucket.unwrap()
+    }
+
+    pub fn words(&self) -> impl Iterator<Item = &'a [u16]> + '_ {
+        let bucket = self.bucket;
+        self.indices.iter().map(move |idx| bucket.unwrap().word(*idx as usize))
+    }
+
+    pub fn char_mapper(&self) -> &'a Box<dyn CharMapper> {
+        &self.dict.char_mapper
+    }
+
+    pub fn apply_filter<const CNTALPHA:usize>(&self, word_filter: &WordFilter<CNTALPHA>, mode: FilterMode) -> DictionaryView<'a> {
+        self.filter_indices(self.indices.par_iter().copied(), word_filter, mode)
+    }
+
+    // the words `keep` accepts, in the same order
+    pub fn retain<F>(&self, keep: F) -> DictionaryView<'a> where F: Fn(&[u16]) -> bool + Sync {
+        let indices: Vec<u32> = match self.bucket {
+            Some(bucket) => self.indices.par_iter().with_min_len(PAR_MIN_LEN).copied()
+                .filter(|idx| keep(bucket.word(*idx as usize)))
+                .collect(),
+            None => Vec::new()
+        };
+
+        DictionaryView {
+            dict: self.dict,
+            wordlen: self.wordlen,
+            indices,
+            bucket: self.bucket,
+        }
+    }
+
+    // the surviving indices keep the order of `candidates`
+    fn filter_indices<I, const CNTALPHA:usize>(&self, candidates: I, word_filter: &WordFilter<CNTALPHA>, mode: FilterMode) -> DictionaryView<'a>
+        where I: IndexedParallelIterator<Item = u32> {
+        let mut indices: Vec<u32> = Vec::new();
+        if let (true, Some(bucket)) = (usize::from(word_filter.wordlen()) == self.wordlen, self.bucket) {
+            let compiled = word_filter.compile();
+            indices = candidates.with_min_len(PAR_MIN_LEN).filter(|idx| {
+                let cur_word = bucket.word(*idx as usize);
+                let signature = bucket.signature(*idx as usize);
+                let to_include = match mode {
+                    FilterMode::ForExploit => compiled.can_be_answer(cur_word, signature),
+                    FilterMode::ForExploration => compiled.is_explorable(cur_word, signature)
+                };
+                debug_assert_eq!(to_include, match mode {
+                    FilterMode::ForExploit => word_filter.can_be_answer(cur_word),
+                    FilterMode::ForExploration => word_filter.is_explorable(cur_word)
+                });
+                to_include
+            }).collect();
+        }
+
+        DictionaryView {
+            dict: self.dict,
+            wordlen: self.wordlen,
+            indices,
+            bucket: self.bucket,
+        }
+    }
+
     pub fn log_letter_freq(&self)->Vec<f32> {
         let mut res : Vec<f32> = Vec::new();
-        let alpcnt =usize::from(self.char_mapper.alphabet_cnt());
+        let alpcnt =usize::from(self.dict.char_mapper.alphabet_cnt());
         res.resize(alpcnt, 0.0); 
         let mut totalcnt = 0;
 
-        for word in self.words.iter(){
+        for word in self.words(){
             for c in word {
                 res[usize::from(*c) - 1] += 1.0;
             }
@@ -363,24 +1061,72 @@ impl Dictionary {
         res
     }
 
+    // number of words with symbol i+1 at `pos`
+    pub fn letter_locfreq_counts(&self, pos: usize)->Vec<usize> {
+        let mut res: Vec<usize> = vec![0; usize::from(self.dict.char_mapper.alphabet_cnt())];
+        if pos < self.wordlen {
+            for word in self.words(){
+                res[usize::from(word[pos] - 1)] += 1;
+            }
+        }
+        res
+    }
+
+    // number of words containing symbol i+1 anywhere
+    pub fn letter_presence_counts(&self)->Vec<usize> {
+        let mut res: Vec<usize> = vec![0; usize::from(self.dict.char_mapper.alphabet_cnt())];
+        let mut seen: Vec<bool> = vec![false; res.len()];
+        for word in self.words(){
+            seen.iter_mut().for_each(|v| *v = false);
+            for c in word {
+                let c = usize::from(*c - 1);
+                if !seen[c] {
+                    seen[c] = true;
+                    res[c] += 1;
+                }
+            }
+        }
+        res
+    }
+
     pub fn log_letter_locfreq(&self, pos: usize)->Vec<f32> {
-        let mut res : Vec<f32> = Vec::new();
-        let alpcnt =usize::from(self.char_mapper.alphabet_cnt());
-        res.resize(alpcnt, 0.0); 
-        let mut totalcnt = 0;
+        let counts = self.letter_locfreq_counts(pos);
+        let totalcnt = if pos < self.wordlen { self.len() } else { 0 };
 
-        for word in self.words.iter(){
-            if word.len() > pos {
-                let c = word[pos] - 1;
-                res[usize::from(c)] += 1.0;
-                totalcnt += 1;
-            } 
+        let mut res : Vec<f32> = Vec::new();
+        for cnt in counts.iter() {
+            res.push(-((f64::from(*cnt as u32 + 1) / (1.0 + f64::from(totalcnt as u32))) as f32).ln());
         }
+        res
+    }
+
+    // the k lowest scores. every chunk keeps its own k best and the chunks are merged; since
+    // (score, index) pairs are totally ordered the result is the same as a serial scan.
+    fn top_k<F>(&self, k: usize, score: F) -> Vec<(f32, Vec<u16>)>
+        where F: Fn(&[u16]) -> f32 + Sync {
+        let push = |mut heap: BinaryHeap<(NotNan<f32>, u32)>, item: (NotNan<f32>, u32)| {
+            heap.push(item);
+            if heap.len() > k {
+                heap.pop();
+            }
+            heap
+        };
 
+        let mut score_heap = match self.bucket {
+            Some(bucket) => self.indices.par_iter()
+                .with_min_len(PAR_MIN_LEN)
+                .map(|idx| (NotNan::new(score(bucket.word(*idx as usize))).unwrap(), *idx))
+                .fold(BinaryHeap::new, push)
+                .reduce(BinaryHeap::new, |a, b| b.into_iter().fold(a, push)),
+            None => BinaryHeap::new()
+        };
 
-        for i in 0..alpcnt {
-            res[i] = -((f64::from(res[i] + 1.0) / (1.0 + f64::from(totalcnt as u32))) as f32).ln();
+        let mut res: Vec<(f32, Vec<u16>)> = Vec::new();
+        while score_heap.len() > 0 {
+            let (score, wordidx) = score_heap.pop().unwrap();
+            res.push((score.into_inner(), self.bucket().word(wordidx as usize).to_vec()));
         }
+        res.reverse();
         res
     }
 
@@ -390,13 +1136,10 @@ impl Dictionary {
             worst_score = max(NotNan::new(*s).unwrap(), NotNan::new(worst_score).unwrap()).into_inner();
         }
 
-        let mut score_heap: BinaryHeap<(NotNan<f32>, usize)> = BinaryHeap::new();
-        for (wordidx, word) in self.words.iter().enumerate() {
+        let cntalpha = usize::from(self.dict.char_mapper.alphabet_cnt());
+        self.top_k(k, |word| {
             let mut cur_score: f32 = 0.0;
-
-            let cntalpha = usize::from(self.char_mapper.alphabet_cnt());
-            let mut incl_char: Vec<bool> = Vec::new();
-            incl_char.resize(cntalpha, false);
+            let mut incl_char = [false; MAX_ALPHABET];
 
             for c in word {
                 let cidx = usize::from(*c - 1);
@@ -416,95 +1159,48 @@ impl Dictionary {
                     }
                 }
             }
-
-            score_heap.push((NotNan::new(cur_score).unwrap(), wordidx));
-            if score_heap.len() > k {
-                score_heap.pop();
-            }
-        }
-
-        let mut res: Vec<(f32, Vec<u16>)> = Vec::new();
-        while score_heap.len() > 0 {
-            let (score, wordidx) = score_heap.pop().unwrap();
-            res.push((score.into_inner(), self.words[wordidx].clone()));
-        }
-        res.reverse();
-        res
+            cur_score
+        })
     }
 
-    pub fn find_best_words_to_exploit(&self, k: usize, locfreq:Vec<Vec<f32>>) -> Vec<(f32, Vec<u16>)> {
-        let mut score_heap: BinaryHeap<(NotNan<f32>, usize)> = BinaryHeap::new();
-        for (wordidx, word) in self.words.iter().enumerate() {
+    pub fn find_best_words_to_exploit(&self, k: usize, locfreq:Vec<Vec<f32>>, past: PastAnswerMode) -> Vec<(f32, Vec<u16>)> {
+        let past_answer_set = &self.dict.past_answer_set;
+        self.top_k(k, |word| {
             let mut cur_score: f32 = 0.0;
             for (cidx, c) in word.iter().enumerate() {
                 cur_score += locfreq[cidx][usize::from(*c - 1)];
             }
-            score_heap.push((NotNan::new(cur_score).unwrap(), wordidx));
-            if score_heap.len() > k {
-                score_heap.pop();
-            }
-        }
-
-        let mut res: Vec<(f32, Vec<u16>)> = Vec::new();
-        while score_heap.len() > 0 {
-            let (score, wordidx) = score_heap.pop().unwrap();
-            res.push((score.into_inner(), self.words[wordidx].clone()));
-        }
-        res.reverse();
-        res
-    }
-
-    pub fn apply_filter<const CNTALPHA:usize>(&self, word_filter: &WordFilter<CNTALPHA>, mode: FilterMode) -> Dictionary {
-        let mut words:Vec<Vec<u16>> = Vec::new();
-        for cur_word in self.words.iter() {
-            let to_include = match mode {
-                FilterMode::ForExploit => word_filter.can_be_answer(cur_word),
-                FilterMode::ForExploration => word_filter.is_explorable(cur_word)
-            };
-            if to_include {
-                words.push(cur_word.clone());
+            // excluded past answers are not in the view to begin with
+            match past {
+                PastAnswerMode::Downweight if past_answer_set.contains(word) => cur_score + PAST_ANSWER_PENALTY,
+                _ => cur_score
             }
-        }
-
-        Dictionary {
-            name: self.name.clone(),
-            words: words,
-            char_mapper: self.char_mapper.clone(),
-        }
-    }
-
-    pub fn get_word(&self, pos: usize)->Option<String> {
-        if self.words.len() <= pos {
-            return None;
-        }
-        return Some(self.char_mapper.unmap_word(&self.words[pos]));
-    }
-
-    pub fn get_char_mapper(&self)->Box<dyn CharMapper> {
-        self.char_mapper.clone_box()
+        })
     }
 }
 
 pub struct DictionaryService {
     reg: HashMap<String, RwLock<Arc<Dictionary>>>,
+    sources: HashMap<String, DictionaryConfig>,
 }
 
 impl DictionaryService {
     pub fn new() -> DictionaryService {
-        DictionaryService { reg: HashMap::new() }
+        DictionaryService { reg: HashMap::new(), sources: HashMap::new() }
     }
 
-    pub fn load(&mut self, lang: &String, path: String, charset:&String) -> Result<(), String> {
-        let char_mapper: Box<dyn CharMapper + Sync + Send> = match charset {
-            _ => Box::new(EnglishCharMapper{}),
-        };
-
-        let dict = RwLock::new(Arc::new(Dictionary::from_file(lang, path, char_mapper).unwrap()));
-        self.reg.insert(lang.clone() , dict);
+    pub fn load(&mut self, conf: &DictionaryConfig) -> Result<(), String> {
+        let dict = Dictionary::from_config(conf)?;
+        self.register(conf, dict);
 
         Ok(())
     }
 
+    pub fn register(&mut self, conf: &DictionaryConfig, dict: Dictionary) {
+        self.reg.insert(conf.lang.clone() , RwLock::new(Arc::new(dict)));
+        self.sources.insert(conf.lang.clone(), conf.clone());
+    }
+
     pub fn list(&self)->Vec<String> {
         let mut res:Vec<String> = Vec::new();
 
@@ -518,6 +1214,60 @@ impl DictionaryService {
     pub fn get(&self, lang: &String) -> Option<&RwLock<Arc<Dictionary>>> {
         self.reg.get(lang)
     }
+
+    pub fn source(&self, lang: &String) -> Option<&DictionaryConfig> {
+        self.sources.get(lang)
+    }
+
+    pub fn info(&self, lang: &String) -> Option<DictionaryInfo> {
+        let dict = self.reg.get(lang)?.read().unwrap().clone();
+        let conf = self.sources.get(lang)?;
+
+        let word_cnt_per_len: BTreeMap<usize, usize> = dict.buckets.iter()
+            .map(|(wordlen, bucket)| (*wordlen, bucket.len()))
+            .collect();
+
+        Some(DictionaryInfo {
+            lang: lang.clone(),
+            charset: conf.charset(),
+            alphabet_cnt: dict.char_mapper.alphabet_cnt(),
+            word_cnt: dict.word_cnt(),
+            word_cnt_per_len,
+            answer_cnt: dict.answers.len(),
+            past_answer_cnt: dict.past_answers.len(),
+            loaded_at: dict.loaded_at.duration_since(SystemTime::UNIX_EPOCH).map_or(0, |d| d.as_secs()),
+            metadata: conf.metadata.clone(),
+        })
+    }
+
+    pub fn detail(&self, lang: &String) -> Option<DictionaryDetail> {
+        let info = self.info(lang)?;
+        let dict = self.reg.get(lang)?.read().unwrap().clone();
+        let conf = self.sources.get(lang)?;
+
+        let report = dict.load_report.clone().unwrap_or_default();
+        Some(DictionaryDetail {
+            alphabet: (1..=info.alphabet_cnt).map(|n| dict.char_mapper.unmap_symbol(n)).collect(),
+            info,
+            path: conf.path.clone(),
+            answers_path: conf.answers.clone(),
+            past_answers_path: conf.past_answers_path(),
+            skipped_cnt: report.skipped.len(),
+            modified_cnt: report.modified.len(),
+            duplicate_cnt: report.duplicates.len(),
+            past_answers_skipped_cnt: report.past_answers_skipped.len(),
+        })
+    }
+
+    pub fn replace(&self, lang: &String, dict: Dictionary) -> Result<(), String> {
+        match self.reg.get(lang) {
+            Some(slot) => {
+                *slot.write().unwrap() = Arc::new(dict);
+                Ok(())
+            }
+            None => Err(format!("dictionary {} not found", lang))
+        }
+    }
 }
 
 lazy_static! {
@@ -526,4 +1276,260 @@ lazy_static! {
 
 pub fn get_dict_service()->&'static Arc<RwLock<DictionaryService>> {
     &DICT_SERVICE
-}
\ No newline at end of file
+}
+
+// keep our own Arc so a reload can swap the dictionary while we are working
+pub fn get_dictionary(lang: &String) -> Result<Arc<Dictionary>, String> {
+    match get_dict_service().read().unwrap().get(lang) {
+        Some(dictarc) => Ok(dictarc.read().unwrap().clone()),
+        None => Err(format!("dictionary {} not found", lang))
+    }
+}
+
+// rebuilds the dictionary on a background thread and swaps the Arc once it is loaded.
+// requests that already hold the previous Arc finish with the old version.
+pub fn reload_in_background(lang: &String) -> Result<thread::JoinHandle<Result<(), String>>, String> {
+    let conf = match get_dict_service().read().unwrap().source(lang) {
+        Some(conf) => conf.clone(),
+        None => return Err(format!("dictionary {} not found", lang))
+    };
+
+    Ok(thread::spawn(move || {
+        let res = Dictionary::from_config(&conf)
+            .and_then(|dict| get_dict_service().read().unwrap().replace(&conf.lang, dict));
+        if let Err(e) = &res {
+            eprintln!("failed to reload dictionary {}: {}", conf.lang, e);
+        }
+        res
+    }))
+}
+
+// `YYYY-MM-DD word` lines, skipping blank lines and '#' comments. also returns the lines that were malformed
+pub fn read_past_answers(path: &String, char_mapper: &Box<dyn CharMapper>) -> Result<(Vec<PastAnswer>, Vec<LoadIssue>), String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    let mut res: Vec<PastAnswer> = Vec::new();
+    let mut skipped: Vec<LoadIssue> = Vec::new();
+    for (lineidx, line) in content.lines().enumerate() {
+        let text = line;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let skip = |reason: String| LoadIssue { line: lineidx + 1, text: text.to_string(), reason };
+
+        let mut fields = line.split_whitespace();
+        let (date, word) = match (fields.next(), fields.next()) {
+            (Some(date), Some(word)) => (date, word),
+            _ => {
+                skipped.push(skip("expected `YYYY-MM-DD word`".to_string()));
+                continue;
+            }
+        };
+        let days = match crate::date::parse_date(date) {
+            Ok(days) => days,
+            Err(e) => {
+                skipped.push(skip(e));
+                continue;
+            }
+        };
+        let mapped = match char_mapper.try_map_word(word) {
+            Some(mapped) if !mapped.is_empty() => mapped,
+            _ => {
+                skipped.push(skip(format!("invalid word {}", word)));
+                continue;
+            }
+        };
+        res.push(PastAnswer {
+            date: crate::date::format_date(days),
+            word: char_mapper.unmap_word(&mapped),
+        });
+    }
+    res.sort_by(|a, b| a.date.cmp(&b.date));
+    Ok((res, skipped))
+}
+
+fn source_mtime(conf: &DictionaryConfig) -> Option<SystemTime> {
+    let alphabet_path = Path::new(&conf.path).with_file_name(format!("{}.alphabet", conf.charset()));
+    let mut paths = vec![Path::new(&conf.path).to_path_buf(), alphabet_path];
+    if let Some(answers) = &conf.answers {
+        paths.push(Path::new(answers).to_path_buf());
+    }
+    paths.push(Path::new(&conf.past_answers_path()).to_path_buf());
+
+    paths.iter()
+        .filter_map(|path| fs::metadata(path).and_then(|meta| meta.modified()).ok())
+        .max()
+}
+
+// polls the files behind every registered dictionary and reloads the ones that changed.
+pub fn watch_dictionaries(interval: Duration) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut last_seen: HashMap<String, Option<SystemTime>> = HashMap::new();
+        loop {
+            let sources: Vec<DictionaryConfig> = {
+                let svc = get_dict_service().read().unwrap();
+                svc.list().iter().filter_map(|lang| svc.source(lang).cloned()).collect()
+            };
+
+            for conf in sources.iter() {
+                let mtime = source_mtime(conf);
+                match last_seen.insert(conf.lang.clone(), mtime) {
+                    Some(prev) if prev != mtime => {
+                        if let Ok(handle) = reload_in_background(&conf.lang) {
+                            let _ = handle.join();
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            thread::sleep(interval);
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::splitmix64;
+
+    fn random_word(state: &mut u64, wordlen: usize, alphabet: u64) -> Vec<u16> {
+        (0..wordlen).map(|_| (splitmix64(state) % alphabet) as u16 + 1).collect()
+    }
+
+    // the compiled filter has to agree with the reference checks on every word of the bucket
+    fn assert_same(filter: &WordFilter<MAX_ALPHABET>, bucket: &WordBucket) {
+        let compiled = filter.compile();
+        for idx in 0..bucket.len() {
+            let (word, signature) = (bucket.word(idx), bucket.signature(idx));
+            assert_eq!(compiled.can_be_answer(word, signature), filter.can_be_answer(word), "can_be_answer({:?}) of {:?}", word, filter);
+            assert_eq!(compiled.is_explorable(word, signature), filter.is_explorable(word), "is_explorable({:?}) of {:?}", word, filter);
+        }
+    }
+
+    // small alphabets so that words repeat letters a lot; one wide one to reach the high bits
+    fn random_bucket(state: &mut u64, wordlen: usize, alphabet: u64) -> WordBucket {
+        let mut bucket = WordBucket::new(wordlen);
+        for _ in 0..500 {
+            bucket.push(&random_word(state, wordlen, alphabet));
+        }
+        bucket
+    }
+
+    #[test]
+    fn compiled_filter_matches_wordle_history() {
+        let char_mapper = EnglishCharMapper {};
+        let mut bucket = WordBucket::new(5);
+        for word in ["eerie", "there", "speed", "abide", "geese", "erase", "melee", "tease", "sheep", "elder"].iter() {
+            bucket.push(&char_mapper.map_word(&word.to_string()));
+        }
+
+        for guess in bucket.iter() {
+            for answer in bucket.iter() {
+                let filter = WordFilter::<MAX_ALPHABET>::from_wordle(5, &vec![guess.to_vec()], &vec![wordle_response(guess, answer)]).unwrap();
+                assert!(filter.can_be_answer(answer));
+                assert_same(&filter, &bucket);
+            }
+        }
+
+        // `eerie` against `there`: one e is green, one yellow and the third black, so exactly two
+        let filter = WordFilter::<MAX_ALPHABET>::from_wordle(5, &vec![char_mapper.map_word(&"eerie".to_string())], &vec![parse_wordle_resp("10102")]).unwrap();
+        assert_eq!(filter.cnt_constraint[usize::from(char_mapper.map_char('e') - 1)], CharConstraint::ShouldContainExactly(2));
+        assert_eq!(filter.cnt_constraint[usize::from(char_mapper.map_char('r') - 1)], CharConstraint::ShouldContainAtLeast(1));
+    }
+
+    #[test]
+    fn compiled_filter_matches_random_history() {
+        let mut state = 1;
+        for (wordlen, alphabet) in [(3, 3), (5, 4), (5, 26), (7, 5), (6, 64)].iter() {
+            let bucket = random_bucket(&mut state, *wordlen, *alphabet);
+            for _ in 0..50 {
+                let answer = bucket.word((splitmix64(&mut state) % bucket.len() as u64) as usize).to_vec();
+                let mut trial: Vec<Vec<u16>> = Vec::new();
+                let mut resp: Vec<Vec<WordleResp>> = Vec::new();
+                for _ in 0..(splitmix64(&mut state) % 4) {
+                    let guess = random_word(&mut state, *wordlen, *alphabet);
+                    resp.push(wordle_response(&guess, &answer));
+                    trial.push(guess);
+                }
+                let filter = WordFilter::<MAX_ALPHABET>::from_wordle(*wordlen as u16, &trial, &resp).unwrap();
+                assert!(filter.can_be_answer(&answer));
+                assert_same(&filter, &bucket);
+            }
+        }
+    }
+
+    #[test]
+    fn compiled_filter_matches_arbitrary_constraints() {
+        let mut state = 2;
+        for (wordlen, alphabet) in [(4, 3), (5, 6), (6, 64)].iter() {
+            let bucket = random_bucket(&mut state, *wordlen, *alphabet);
+            for _ in 0..200 {
+                let mut cnt_constraint = [CharConstraint::ShouldContainAtLeast(0); MAX_ALPHABET];
+                for ch in 0..*alphabet as usize {
+                    // counts up to wordlen + 1, which nothing can satisfy
+                    let cnt = (splitmix64(&mut state) % (*wordlen as u64 + 2)) as u8;
+                    cnt_constraint[ch] = match splitmix64(&mut state) % 4 {
+                        0 => CharConstraint::ShouldNotContain,
+                        1 => CharConstraint::ShouldContainExactly(cnt),
+                        _ => CharConstraint::ShouldContainAtLeast(cnt / 2),
+                    };
+                }
+                let mut prohib_chars: Vec<[bool; MAX_ALPHABET]> = vec![[false; MAX_ALPHABET]; *wordlen];
+                let mut match_chars: Vec<u16> = vec![0; *wordlen];
+                for pos in 0..*wordlen {
+                    for ch in 0..*alphabet as usize {
+                        prohib_chars[pos][ch] = splitmix64(&mut state) % 8 == 0;
+                    }
+                    if splitmix64(&mut state) % 4 == 0 {
+                        match_chars[pos] = (splitmix64(&mut state) % alphabet) as u16 + 1;
+                    }
+                }
+                let filter = WordFilter::<MAX_ALPHABET>::new(*wordlen as u16, &cnt_constraint, &prohib_chars, &match_chars);
+                assert_same(&filter, &bucket);
+            }
+        }
+    }
+
+    #[test]
+    fn response_code_matches_response() {
+        let mut state = 4;
+        for (wordlen, alphabet) in [(1, 2), (5, 3), (5, 26), (12, 4), (40, 3)].iter() {
+            assert_eq!(format_response_code(solved_response_code(*wordlen), *wordlen), "2".repeat(*wordlen));
+            for _ in 0..2000 {
+                let guess = random_word(&mut state, *wordlen, *alphabet);
+                let answer = random_word(&mut state, *wordlen, *alphabet);
+                let code = wordle_response_code(&guess, &answer);
+                assert_eq!(format_response_code(code, *wordlen), format_wordle_resp(&wordle_response(&guess, &answer)));
+                assert_eq!(code == solved_response_code(*wordlen), guess == answer);
+            }
+        }
+    }
+
+    #[test]
+    fn top_k_matches_serial_sort() {
+        let mut state = 3;
+        let mut dict = Dictionary::new(&"test".to_string(), Box::new(EnglishCharMapper {}));
+        let mut bucket = WordBucket::new(5);
+        for _ in 0..20000 {
+            bucket.push(&random_word(&mut state, 5, 26));
+        }
+        dict.buckets.insert(5, bucket);
+
+        // few distinct scores, so most of the order comes from breaking ties by index
+        let score = |word: &[u16]| f32::from((word[0] + word[1]) % 4);
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(4).build().unwrap();
+        for view in [dict.view(5), dict.view(5).retain(|word| word[2] % 2 == 0)].iter() {
+            let mut expected: Vec<(f32, u32)> = view.indices.iter().map(|idx| (score(view.bucket().word(*idx as usize)), *idx)).collect();
+            expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            for k in [1, 10, 1000, 5000, view.len() + 1].iter() {
+                let expected: Vec<(f32, Vec<u16>)> = expected.iter().take(*k)
+                    .map(|(score, idx)| (*score, view.bucket().word(*idx as usize).to_vec()))
+                    .collect();
+                for _ in 0..3 {
+                    assert_eq!(pool.install(|| view.top_k(*k, score)), expected, "k = {}", k);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,273 @@
+use std::{collections::{HashMap, hash_map::RandomState}, hash::{BuildHasher, Hasher}, sync::{Mutex, atomic::{AtomicU64, Ordering}}, time::{Duration, Instant, SystemTime, UNIX_EPOCH}};
+use rocket::State;
+use rocket_contrib::json::Json;
+
+use crate::{Resp, respond};
+use crate::daily;
+use crate::stats::{self, StatsStore};
+use crate::dict::{self, Dictionary, WordleResp, get_dictionary};
+
+pub const MAX_GUESSES: usize = 6;
+// games nobody touched for this long are dropped
+pub const GAME_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+// splitmix64; good enough to pick answers and game ids
+pub fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+struct Game {
+    lang: String,
+    wordlen: usize,
+    answer: String,
+    hard_mode: bool,
+    puzzle: Option<String>,
+    player: Option<String>,
+    guesses: Vec<String>,
+    resps: Vec<String>,
+    touched: Instant,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct GameState {
+    pub id: String,
+    pub lang: String,
+    pub wordlen: usize,
+    pub hard_mode: bool,
+    // id of the daily puzzle being played
+    pub puzzle: Option<String>,
+    // finished games of a player are kept in the stats store
+    pub player: Option<String>,
+    pub guesses: Vec<String>,
+    pub resps: Vec<String>,
+    pub max_guesses: usize,
+    pub finished: bool,
+    pub won: bool,
+    // revealed once the game is over
+    pub answer: Option<String>,
+}
+
+impl Game {
+    fn won(&self) -> bool {
+        self.resps.last().map_or(false, |resp| resp.chars().all(|c| c == '2'))
+    }
+
+    fn finished(&self) -> bool {
+        self.won() || self.guesses.len() >= MAX_GUESSES
+    }
+
+    fn state(&self, id: &String) -> GameState {
+        GameState {
+            id: id.clone(),
+            lang: self.lang.clone(),
+            wordlen: self.wordlen,
+            hard_mode: self.hard_mode,
+            puzzle: self.puzzle.clone(),
+            player: self.player.clone(),
+            guesses: self.guesses.clone(),
+            resps: self.resps.clone(),
+            max_guesses: MAX_GUESSES,
+            finished: self.finished(),
+            won: self.won(),
+            answer: if self.finished() { Some(self.answer.clone()) } else { None },
+        }
+    }
+}
+
+// the words a game of this length can be about: the answer list if there is one
+pub fn answer_pool(dict: &Dictionary, wordlen: usize) -> Vec<Vec<u16>> {
+    let answers: Vec<Vec<u16>> = dict.answers.iter().filter(|word| word.len() == wordlen).cloned().collect();
+    if !answers.is_empty() {
+        return answers;
+    }
+    dict.view(wordlen).words().map(|word| word.to_vec()).collect()
+}
+
+// every hint revealed so far has to be used: greens stay in place, other hinted letters stay in the word
+fn check_hard_mode(guess: &Vec<u16>, trial: &Vec<Vec<u16>>, resp: &Vec<Vec<WordleResp>>, char_mapper: &Box<dyn dict::CharMapper>) -> Result<(), String> {
+    for (prev, prev_resp) in trial.iter().zip(resp.iter()) {
+        for pos in 0..prev.len() {
+            if prev_resp[pos] == WordleResp::Green && guess[pos] != prev[pos] {
+                return Err(format!("letter {} must be at position {}", char_mapper.unmap_symbol(prev[pos]), pos + 1));
+            }
+        }
+        for pos in 0..prev.len() {
+            if prev_resp[pos] == WordleResp::Black {
+                continue;
+            }
+            let hinted = (0..prev.len()).filter(|i| prev[*i] == prev[pos] && prev_resp[*i] != WordleResp::Black).count();
+            if guess.iter().filter(|c| **c == prev[pos]).count() < hinted {
+                return Err(format!("guess must contain {}", char_mapper.unmap_symbol(prev[pos])));
+            }
+        }
+    }
+    Ok(())
+}
+
+pub struct GameService {
+    games: Mutex<HashMap<String, Game>>,
+    // picks answers; splitmix64 can be run backwards, so nothing drawn from it is ever shown
+    rng: Mutex<u64>,
+    // game ids are a keyed hash of a counter. RandomState keys come from the OS
+    ids: RandomState,
+    id_cnt: AtomicU64,
+}
+
+impl GameService {
+    pub fn new() -> GameService {
+        let ids = RandomState::new();
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u128(SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_nanos()));
+        GameService {
+            games: Mutex::new(HashMap::new()),
+            rng: Mutex::new(hasher.finish()),
+            ids,
+            id_cnt: AtomicU64::new(0),
+        }
+    }
+
+    fn next_random(&self) -> u64 {
+        splitmix64(&mut self.rng.lock().unwrap())
+    }
+
+    fn next_id(&self) -> String {
+        let cnt = self.id_cnt.fetch_add(1, Ordering::Relaxed);
+        let half = |part: u64| {
+            let mut hasher = self.ids.build_hasher();
+            hasher.write_u64(cnt);
+            hasher.write_u64(part);
+            hasher.finish()
+        };
+        format!("{:016x}{:016x}", half(0), half(1))
+    }
+
+    pub fn start(&self, lang: &String, wordlen: usize, seed: Option<u64>, hard_mode: bool, player: Option<String>) -> Result<GameState, String> {
+        let dict = get_dictionary(lang)?;
+        let pool = answer_pool(&dict, wordlen);
+        if pool.is_empty() {
+            return Err(format!("no words of length {}", wordlen));
+        }
+        let mut state = seed.unwrap_or_else(|| self.next_random());
+        let answer = dict.char_mapper.unmap_word(&pool[(splitmix64(&mut state) % pool.len() as u64) as usize]);
+        Ok(self.insert(lang, wordlen, answer, hard_mode, None, player))
+    }
+
+    pub fn start_daily(&self, lang: &String, wordlen: usize, date: &str, hard_mode: bool, player: Option<String>) -> Result<GameState, String> {
+        let dict = get_dictionary(lang)?;
+        let puzzle = daily::puzzle(lang, wordlen, date)?;
+        let answer = daily::answer(&dict, &puzzle)?;
+        Ok(self.insert(lang, wordlen, answer, hard_mode, Some(puzzle.id), player))
+    }
+
+    fn insert(&self, lang: &String, wordlen: usize, answer: String, hard_mode: bool, puzzle: Option<String>, player: Option<String>) -> GameState {
+        let id = self.next_id();
+        let game = Game {
+            lang: lang.clone(),
+            wordlen,
+            answer,
+            hard_mode,
+            puzzle,
+            player,
+            guesses: Vec::new(),
+            resps: Vec::new(),
+            touched: Instant::now(),
+        };
+        let state = game.state(&id);
+
+        let mut games = self.games.lock().unwrap();
+        games.retain(|_, game| game.touched.elapsed() < GAME_TTL);
+        games.insert(id, game);
+        state
+    }
+
+    pub fn state(&self, id: &String) -> Result<GameState, String> {
+        match self.games.lock().unwrap().get(id) {
+            Some(game) => Ok(game.state(id)),
+            None => Err(format!("game {} not found", id))
+        }
+    }
+
+    pub fn guess(&self, id: &String, guess: &String) -> Result<GameState, String> {
+        let mut games = self.games.lock().unwrap();
+        let game = match games.get_mut(id) {
+            Some(game) => game,
+            None => return Err(format!("game {} not found", id))
+        };
+        if game.finished() {
+            return Err("game is over".to_string());
+        }
+
+        let dict = get_dictionary(&game.lang)?;
+        let char_mapper = dict.get_char_mapper();
+        let mapped_guess = char_mapper.try_map_word(guess).ok_or(format!("invalid word {:?}", guess))?;
+        let is_word = dict.buckets.get(&game.wordlen).map_or(false, |bucket| bucket.iter().any(|word| word == mapped_guess.as_slice()));
+        if mapped_guess.len() != game.wordlen || !is_word {
+            return Err(format!("{} is not in the word list", guess));
+        }
+
+        if game.hard_mode {
+            let trial: Vec<Vec<u16>> = game.guesses.iter().map(|word| char_mapper.map_word(word)).collect();
+            let resp: Vec<Vec<WordleResp>> = game.resps.iter().map(|resp| dict::parse_wordle_resp(resp)).collect();
+            check_hard_mode(&mapped_guess, &trial, &resp, &char_mapper)?;
+        }
+
+        let resp = dict::wordle_response(&mapped_guess, &char_mapper.map_word(&game.answer));
+        game.guesses.push(char_mapper.unmap_word(&mapped_guess));
+        game.resps.push(dict::format_wordle_resp(&resp));
+        game.touched = Instant::now();
+        Ok(game.state(id))
+    }
+}
+
+#[derive(Deserialize)]
+pub struct NewGameReq {
+    lang: String,
+    wordlen: usize,
+    // the same seed always picks the same answer
+    seed: Option<u64>,
+    // play the daily puzzle of this date (`YYYY-MM-DD` or `today`) instead
+    daily: Option<String>,
+    player: Option<String>,
+    #[serde(default)]
+    hard_mode: bool,
+}
+
+#[derive(Deserialize)]
+pub struct GuessReq {
+    guess: String,
+}
+
+#[post("/game", data="<req>")]
+pub fn start(games: State<GameService>, req: Json<NewGameReq>) -> Json<Resp<GameState>> {
+    if let Some(player) = &req.player {
+        if !stats::is_valid_player(player) {
+            return respond(Err(format!("invalid player id {}", player)));
+        }
+    }
+    match &req.daily {
+        Some(date) => respond(games.start_daily(&req.lang, req.wordlen, date, req.hard_mode, req.player.clone())),
+        None => respond(games.start(&req.lang, req.wordlen, req.seed, req.hard_mode, req.player.clone()))
+    }
+}
+
+#[get("/game/<id>")]
+pub fn state(games: State<GameService>, id: String) -> Json<Resp<GameState>> {
+    respond(games.state(&id))
+}
+
+#[post("/game/<id>/guess", data="<req>")]
+pub fn guess(games: State<GameService>, store: State<StatsStore>, id: String, req: Json<GuessReq>) -> Json<Resp<GameState>> {
+    let res = games.guess(&id, &req.guess);
+    if let Ok(state) = &res {
+        if let (true, Some(player)) = (state.finished, &state.player) {
+            if let Err(e) = store.record(player, state) {
+                eprintln!("failed to record game {}: {}", state.id, e);
+            }
+        }
+    }
+    respond(res)
+}
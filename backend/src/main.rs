@@ -1,15 +1,28 @@
 #![feature(proc_macro_hygiene, decl_macro)]
+pub mod admin;
+pub mod cache;
+pub mod cli;
+pub mod config;
+pub mod daily;
+pub mod date;
 pub mod dict;
+pub mod game;
+pub mod pattern;
+pub mod solver;
+pub mod stats;
 
 #[macro_use] extern crate rocket;
 #[macro_use] extern crate serde_derive;
 
-use dict::{WordFilter, get_dict_service};
-use rocket::routes;
+use cache::{CacheStats, TtlLruCache};
+use dict::{WordFilter, DictionaryDetail, DictionaryInfo, LoadReport, MAX_ALPHABET, get_dict_service, get_dictionary};
+use rocket::{State, routes};
+use solver::{Heatmap, OpeningBook, PredictOptions, PredictionResult, Review, Strategy, WhatIf, history_filter, predict_with};
 use serde::{Serialize, Deserialize};
 use rocket_contrib::json::{Json};
 use rocket_contrib::serve::StaticFiles;
 use serde_derive::Deserialize;
+use std::time::Duration;
 
 #[get("/")]
 fn index() -> &'static str {
@@ -22,139 +35,235 @@ struct PredictReq {
     lang: String,
     trial: Vec<String>,
     resp: Vec<String>,
-    k: Option<usize>
+    k: Option<usize>,
+    strategy: Option<Strategy>,
+    // how long the lookahead strategy may search
+    time_budget_ms: Option<u64>,
+    #[serde(flatten)]
+    opts: PredictOptions
 }
 
 #[derive(Serialize, Deserialize)]
-struct Resp<T> where T:Serialize{
-    success: bool,
-    msg: Option<String>,
-    result: Option<T>
+pub struct Resp<T> where T:Serialize{
+    pub success: bool,
+    pub msg: Option<String>,
+    pub result: Option<T>
 }
 
-#[derive(Serialize, Deserialize)]
-struct PredictionResult {
-    to_exploit: Vec<(f32, String)>,
-    to_explore: Vec<(f32, String)>,
-}
-
-#[post("/pred", data="<req>")]
-fn pred(req: Json<PredictReq>) -> Json<Resp<PredictionResult>> {
-    if req.wordlen < 0 || req.wordlen > 10 {
-        return Json(Resp {
+pub fn respond<T>(res: Result<T, String>) -> Json<Resp<T>> where T: Serialize {
+    match res {
+        Ok(res) => Json(Resp {
+            success: true,
+            msg: None,
+            result: Some(res)
+        }),
+        Err(msg) => Json(Resp {
             success: false,
-            msg: Some(format!("wordlen({}) out of range", req.wordlen)),
+            msg: Some(msg),
             result: None
-        });
+        })
     }
+}
 
-    if req.trial.len() > 10 || req.resp.len() > 10 {
-        return Json(Resp {
-            success: false,
-            msg: Some(format!("trial/resp is too long")),
-            result: None
-        });
+// histories that lead to the same WordFilter share an entry. the dictionary generation
+// is part of the key, so entries computed before a reload are never served again.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct PredictionKey {
+    lang: String,
+    generation: u64,
+    wordlen: usize,
+    filter: WordFilter<MAX_ALPHABET>,
+    strategy: Strategy,
+    k: usize,
+    time_budget: Duration,
+    opts: PredictOptions,
+}
+
+type PredictionCache = TtlLruCache<PredictionKey, PredictionResult>;
+
+#[post("/pred", data="<req>")]
+fn pred(cache: State<PredictionCache>, req: Json<PredictReq>) -> Json<Resp<PredictionResult>> {
+    if let Err(msg) = check_history(req.wordlen, &req.trial, &req.resp) {
+        return respond(Err(msg));
     }
 
-    let res = suggest(&req.lang, usize::from(req.wordlen as u16), &req.trial, &req.resp, req.k.unwrap_or(5));
-    if let Err(msg) = res {
-        return Json(Resp {
-            success: false,
-            msg: Some(msg),
-            result: None
-        });
+    if req.time_budget_ms.map_or(false, |ms| ms > 5000) {
+        return respond(Err(format!("time_budget_ms is too large")));
+    }
+    // every suggestion gets scored against all candidates
+    if req.k.map_or(false, |k| k > 100) {
+        return respond(Err(format!("k is too large")));
     }
 
-    let (to_explore, to_exploit ) = res.unwrap();
-    return Json(Resp {
-        success: true ,
-        msg: None,
-        result: Some(PredictionResult {
-            to_exploit: to_exploit,
-            to_explore: to_explore
-        })
-    });
+    let strategy = req.strategy.unwrap_or(Strategy::Frequency);
+    let time_budget = req.time_budget_ms.map_or(solver::DEFAULT_TIME_BUDGET, Duration::from_millis);
+    respond(suggest(&cache, &req.lang, usize::from(req.wordlen as u16), &req.trial, &req.resp, strategy, req.k.unwrap_or(5), time_budget, &req.opts))
+}
+
+#[derive(Serialize, Deserialize)]
+struct WhatIfReq {
+    wordlen: i32,
+    lang: String,
+    trial: Vec<String>,
+    resp: Vec<String>,
+    guess: String
+}
+
+#[post("/whatif", data="<req>")]
+fn whatif(req: Json<WhatIfReq>) -> Json<Resp<WhatIf>> {
+    let res = check_history(req.wordlen, &req.trial, &req.resp)
+        .and_then(|_| get_dictionary(&req.lang))
+        .and_then(|dict| solver::what_if(&dict, req.wordlen as usize, &req.trial, &req.resp, &req.guess));
+    respond(res)
+}
+
+#[derive(Serialize, Deserialize)]
+struct ReviewReq {
+    wordlen: i32,
+    lang: String,
+    trial: Vec<String>,
+    resp: Vec<String>,
+    answer: String
+}
+
+#[post("/review", data="<req>")]
+fn review(req: Json<ReviewReq>) -> Json<Resp<Review>> {
+    let res = check_history(req.wordlen, &req.trial, &req.resp)
+        .and_then(|_| get_dictionary(&req.lang))
+        .and_then(|dict| solver::review(&dict, req.wordlen as usize, &req.trial, &req.resp, &req.answer));
+    respond(res)
+}
+
+#[derive(Serialize, Deserialize)]
+struct HeatmapReq {
+    wordlen: i32,
+    lang: String,
+    trial: Vec<String>,
+    resp: Vec<String>,
+    #[serde(flatten)]
+    opts: PredictOptions
+}
+
+#[post("/heatmap", data="<req>")]
+fn heatmap(req: Json<HeatmapReq>) -> Json<Resp<Heatmap>> {
+    let res = check_history(req.wordlen, &req.trial, &req.resp)
+        .and_then(|_| get_dictionary(&req.lang))
+        .and_then(|dict| solver::heatmap(&dict, req.wordlen as usize, &req.trial, &req.resp, &req.opts));
+    respond(res)
+}
+
+#[get("/cache")]
+fn cache_stats(cache: State<PredictionCache>) -> Json<Resp<CacheStats>> {
+    respond(Ok(cache.stats()))
 }
 
-fn suggest(lang: &String, wordlen:usize, trial:&Vec<String>, resp:&Vec<String>, k:usize)->Result<(Vec<(f32, String)>, Vec<(f32, String)>), String> {
-    let mut res_explore: Vec<(f32, String)> = Vec::new();
-    let mut res_exploit: Vec<(f32, String)> = Vec::new();
+#[get("/dict")]
+fn dict_list() -> Json<Resp<Vec<DictionaryInfo>>> {
+    let svc = get_dict_service().read().unwrap();
+    let mut langs = svc.list();
+    langs.sort();
+
+    respond(Ok(langs.iter().filter_map(|lang| svc.info(lang)).collect()))
+}
+
+#[get("/dict/<lang>")]
+fn dict_detail(lang: String) -> Json<Resp<DictionaryDetail>> {
+    let detail = get_dict_service().read().unwrap().detail(&lang);
+    respond(detail.ok_or(format!("dictionary {} not found", lang)))
+}
+
+#[get("/dict/<lang>/report")]
+fn dict_report(lang: String) -> Json<Resp<LoadReport>> {
+    let svc = get_dict_service().read().unwrap();
+    let report = svc.get(&lang).and_then(|dictarc| dictarc.read().unwrap().load_report.clone());
+    respond(report.ok_or(format!("dictionary {} not found", lang)))
+}
 
+fn check_history(wordlen: i32, trial: &Vec<String>, resp: &Vec<String>) -> Result<(), String> {
+    if wordlen < 0 || wordlen > 10 {
+        return Err(format!("wordlen({}) out of range", wordlen));
+    }
+    if trial.len() > 10 || resp.len() > 10 {
+        return Err(format!("trial/resp is too long"));
+    }
     if trial.len() != resp.len() {
         return Err(format!("input / resp have different lengths"));
     }
+    Ok(())
+}
 
-    {
-        let lck_svc = get_dict_service();
-        let svc = lck_svc.read().unwrap(); 
-        if let Some(dictarc) = svc.get(&lang) {
-            let dict = dictarc.read().unwrap();
-            let char_mapper = dict.get_char_mapper();
-
-            let mut translated_trial: Vec<Vec<u16>> = Vec::new();
-            let mut translated_resp: Vec<Vec<dict::WordleResp>> = Vec::new();
-            for (wordidx, word) in trial.iter().enumerate() {
-                if word.len() == wordlen && resp[wordidx].len() == wordlen {
-                    let cur_word = char_mapper.map_word(word);
-                    let mut cur_resp: Vec<dict::WordleResp> = Vec::new();
-                    for c in resp[wordidx].chars() {
-                        cur_resp.push(
-                        match c {
-                            '1' => dict::WordleResp::Yellow,
-                            '2' => dict::WordleResp::Green,
-                            _ => dict::WordleResp::Black,
-                        })
-                    }
-                    translated_trial.push(cur_word);
-                    translated_resp.push(cur_resp)
-                }
-            }
+fn suggest(cache: &PredictionCache, lang: &String, wordlen:usize, trial:&Vec<String>, resp:&Vec<String>, strategy: Strategy, k:usize, time_budget: Duration, opts: &PredictOptions)->Result<PredictionResult, String> {
+    let dict = get_dictionary(lang)?;
+    opts.validate(&dict, wordlen)?;
 
-            let filter = WordFilter::<26>::from_wordle(wordlen as u16, &translated_trial, &translated_resp);
-            match filter {
-                Ok(word_filter) => {
-                    let exploit_dict = dict.apply_filter(&word_filter, dict::FilterMode::ForExploit);
-                    let explore_dict = dict.apply_filter(&word_filter, dict::FilterMode::ForExploration);
-
-                    let freq = exploit_dict.log_letter_freq();
-                    let is_char_explored = word_filter.is_char_explored();
-                    let mut locfreq :Vec<Vec<f32>> = Vec::new();
-                    for pos in 0..wordlen {
-                        locfreq.push(exploit_dict.log_letter_locfreq(pos));
-                    }
-
-                    let tmp_explore = explore_dict.find_best_words_to_explore(k, &freq, &is_char_explored);
-                    for (cur_score, cur_word) in tmp_explore.iter() {
-                        res_explore.push((*cur_score, char_mapper.unmap_word(cur_word)))
-                    }
-
-                    let tmp_exploit = exploit_dict.find_best_words_to_exploit(k, locfreq);
-                    for (cur_score, cur_word) in tmp_exploit.iter() {
-                        res_exploit.push((*cur_score, char_mapper.unmap_word(cur_word)))
-                    }
-
-                    // print!("{:#?}", word_filter);
-
-                },
-                Err(e) => {
-                    return Err(e)
-                }
-            }
-        } else {
-            return Err("dictioanry not found".to_string());
+    // the first two moves are usually answered from a precomputed book, which only knows the default options
+    if opts.is_default() {
+        if let Some(res) = OpeningBook::get(&dict, wordlen, strategy).and_then(|book| book.lookup(&dict, trial, resp, k)) {
+            return Ok(res);
         }
     }
 
-    Ok((res_explore, res_exploit))
+    let word_filter = history_filter(&dict, wordlen, trial, resp)?;
+    let key = PredictionKey {
+        lang: lang.clone(),
+        generation: dict.generation,
+        wordlen,
+        filter: word_filter.clone(),
+        strategy,
+        k,
+        time_budget,
+        opts: opts.clone(),
+    };
+    if let Some(res) = cache.get(&key) {
+        return Ok(res);
+    }
+
+    let res = predict_with(&dict, &word_filter, wordlen, k, strategy, time_budget, opts);
+    cache.insert(key, res.clone());
+    Ok(res)
 }
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        if let Err(e) = cli::run(&args) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let conf = config::Config::load().unwrap();
+    if let Some(threads) = conf.threads {
+        rayon::ThreadPoolBuilder::new().num_threads(threads).build_global().unwrap();
+    }
     {
         let mut svc = get_dict_service().write().unwrap();
-        svc.load(&"en".to_string(), "./dict/en.txt".to_string(), &"en".to_string()).unwrap();
+        for dict_conf in conf.dictionaries().unwrap().iter() {
+            if let Err(e) = svc.load(dict_conf) {
+                eprintln!("failed to load dictionary {} from {}: {}", dict_conf.lang, dict_conf.path, e);
+            }
+        }
     }
+    if let Some(secs) = conf.watch_interval_secs {
+        dict::watch_dictionaries(Duration::from_secs(secs));
+    }
+
+    let stats_path = conf.stats_path.clone().unwrap_or(config::DEFAULT_STATS_PATH.to_string());
+    let store = stats::StatsStore::open(&stats_path).unwrap();
+
+    let cache = PredictionCache::new(
+        conf.cache_size.unwrap_or(config::DEFAULT_CACHE_SIZE),
+        conf.cache_ttl_secs.map(Duration::from_secs));
+
     rocket::ignite()
+    .manage(conf)
+    .manage(cache)
+    .manage(game::GameService::new())
+    .manage(store)
     .mount("/", StaticFiles::from("./static"))
-    .mount("/api/", routes![index, pred])
+    .mount("/api/", routes![index, pred, whatif, review, heatmap, pattern::pattern, cache_stats, dict_list, dict_detail, dict_report])
+    .mount("/api/", routes![game::start, game::state, game::guess, daily::daily, stats::stats, stats::history])
+    .mount("/api/admin/", routes![admin::reload, admin::create_dict, admin::edit_words, admin::add_past_answer])
     .launch();
 }
\ No newline at end of file
@@ -0,0 +1,125 @@
+use rocket_contrib::json::Json;
+
+use crate::{Resp, respond};
+use crate::dict::{self, CharConstraint, CharMapper, Dictionary, WordFilter, MAX_ALPHABET, get_dictionary};
+
+pub const DEFAULT_PAGE_SIZE: usize = 50;
+pub const MAX_PAGE_SIZE: usize = 500;
+
+#[derive(Deserialize)]
+pub struct PatternReq {
+    lang: String,
+    // one tile per position, `?` (or `_`, `.`) for any letter, e.g. `a?e??`
+    pattern: String,
+    // letters the word must contain; repeat a letter to require it more than once
+    #[serde(default)]
+    include: String,
+    // letters the word must not contain
+    #[serde(default)]
+    exclude: String,
+    // not_at[pos]: letters that may not be at pos
+    #[serde(default)]
+    not_at: Vec<String>,
+    #[serde(default)]
+    offset: usize,
+    limit: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PatternResult {
+    pub total: usize,
+    pub offset: usize,
+    pub words: Vec<String>,
+}
+
+fn is_wildcard(s: &str) -> bool {
+    s == "?" || s == "_" || s == "."
+}
+
+// symbols of `letters`, which must all be in the alphabet
+fn map_letters(char_mapper: &Box<dyn CharMapper>, letters: &str) -> Result<Vec<u16>, String> {
+    let mut res: Vec<u16> = Vec::new();
+    for (v, text) in char_mapper.tokenize(letters.trim()) {
+        if v == 0 {
+            return Err(format!("invalid letter {:?}", text));
+        }
+        res.push(v);
+    }
+    Ok(res)
+}
+
+pub fn pattern_filter(dict: &Dictionary, req: &PatternReq) -> Result<WordFilter<MAX_ALPHABET>, String> {
+    let char_mapper = dict.get_char_mapper();
+
+    let mut match_chars: Vec<u16> = Vec::new();
+    for (v, text) in char_mapper.tokenize(req.pattern.trim()) {
+        if is_wildcard(text) {
+            match_chars.push(0);
+        } else if v == 0 {
+            return Err(format!("invalid letter {:?} in pattern", text));
+        } else {
+            match_chars.push(v);
+        }
+    }
+    let wordlen = match_chars.len();
+    if wordlen == 0 {
+        return Err("empty pattern".to_string());
+    }
+    if wordlen > usize::from(u8::MAX) {
+        return Err("pattern is too long".to_string());
+    }
+    if req.not_at.len() > wordlen {
+        return Err(format!("not_at has more entries than the pattern has letters"));
+    }
+
+    // counts are u8, and more required letters than positions can never match anyway
+    let include = map_letters(&char_mapper, &req.include)?;
+    if include.len() > wordlen {
+        return Err(format!("include has more letters than the pattern has positions"));
+    }
+    let mut cnt_constraint = [CharConstraint::ShouldContainAtLeast(0); MAX_ALPHABET];
+    for c in include {
+        if let CharConstraint::ShouldContainAtLeast(cnt) = cnt_constraint[usize::from(c - 1)] {
+            cnt_constraint[usize::from(c - 1)] = CharConstraint::ShouldContainAtLeast(cnt + 1);
+        }
+    }
+
+    let mut prohib_chars: Vec<[bool; MAX_ALPHABET]> = vec![[false; MAX_ALPHABET]; wordlen];
+    for c in map_letters(&char_mapper, &req.exclude)? {
+        let required = match_chars.contains(&c) || cnt_constraint[usize::from(c - 1)] != CharConstraint::ShouldContainAtLeast(0);
+        if required {
+            return Err(format!("{} is both required and excluded", char_mapper.unmap_symbol(c)));
+        }
+        cnt_constraint[usize::from(c - 1)] = CharConstraint::ShouldNotContain;
+        for pos in 0..wordlen {
+            prohib_chars[pos][usize::from(c - 1)] = true;
+        }
+    }
+    for (pos, letters) in req.not_at.iter().enumerate() {
+        for c in map_letters(&char_mapper, letters)? {
+            prohib_chars[pos][usize::from(c - 1)] = true;
+        }
+    }
+
+    Ok(WordFilter::new(wordlen as u16, &cnt_constraint, &prohib_chars, &match_chars))
+}
+
+pub fn query(dict: &Dictionary, req: &PatternReq) -> Result<PatternResult, String> {
+    let limit = req.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    if limit > MAX_PAGE_SIZE {
+        return Err(format!("limit({}) out of range", limit));
+    }
+
+    let word_filter = pattern_filter(dict, req)?;
+    let matches = dict.apply_filter(&word_filter, dict::FilterMode::ForExploit);
+    Ok(PatternResult {
+        total: matches.len(),
+        offset: req.offset,
+        words: matches.words().skip(req.offset).take(limit).map(|word| dict.char_mapper.unmap_word(&word.to_vec())).collect(),
+    })
+}
+
+#[post("/pattern", data="<req>")]
+pub fn pattern(req: Json<PatternReq>) -> Json<Resp<PatternResult>> {
+    respond(get_dictionary(&req.lang).and_then(|dict| query(&dict, &req)))
+}
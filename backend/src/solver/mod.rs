@@ -0,0 +1,879 @@
+use std::{collections::{BTreeMap, HashMap, HashSet}, fs, path::{Path, PathBuf}, sync::{Arc, RwLock}, time::{Duration, Instant}};
+use lazy_static::lazy_static;
+
+use crate::config::DictionaryConfig;
+use crate::dict::{self, Dictionary, DictionaryView, PastAnswerMode, WordFilter, MAX_ALPHABET, get_dict_service};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Strategy {
+    // letter frequency heuristics behind to_explore / to_exploit
+    Frequency,
+    // re-ranks the best of those by simulating one more move
+    Lookahead,
+}
+
+impl Strategy {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Strategy::Frequency => "frequency",
+            Strategy::Lookahead => "lookahead",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Strategy> {
+        match name {
+            "frequency" => Some(Strategy::Frequency),
+            "lookahead" => Some(Strategy::Lookahead),
+            _ => None
+        }
+    }
+
+    // the word a player following this strategy would enter next
+    pub fn pick(&self, res: &PredictionResult) -> Option<String> {
+        match self {
+            Strategy::Frequency => {
+                if res.candidate_cnt <= 2 || res.to_explore.is_empty() {
+                    res.to_exploit.first().map(|(_, word)| word.clone())
+                } else {
+                    res.to_explore.first().map(|(_, word)| word.clone())
+                }
+            }
+            Strategy::Lookahead => match &res.lookahead {
+                Some(lookahead) if !lookahead.is_empty() => Some(lookahead[0].word.clone()),
+                _ => Strategy::Frequency.pick(res)
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PredictionResult {
+    pub to_exploit: Vec<(f32, String)>,
+    pub to_explore: Vec<(f32, String)>,
+    pub candidate_cnt: usize,
+    // both lists merged and ranked by expected guesses to solve
+    #[serde(default)]
+    pub recommended: Vec<Recommendation>,
+    // filled by the lookahead strategy, best first
+    pub lookahead: Option<Vec<LookaheadScore>>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Recommendation {
+    pub word: String,
+    // guesses until solved, counting this one
+    pub expected_guesses: f32,
+    // whether the word can still be the answer
+    pub is_candidate: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LookaheadScore {
+    pub word: String,
+    // guesses until solved, counting this one
+    pub expected_guesses: f32,
+    // number of distinct feedbacks the guess can get
+    pub bucket_cnt: usize,
+}
+
+impl PredictionResult {
+    pub fn truncate(&self, k: usize) -> PredictionResult {
+        PredictionResult {
+            to_exploit: self.to_exploit.iter().take(k).cloned().collect(),
+            to_explore: self.to_explore.iter().take(k).cloned().collect(),
+            candidate_cnt: self.candidate_cnt,
+            recommended: self.recommended.iter().take(k).cloned().collect(),
+            lookahead: self.lookahead.clone(),
+        }
+    }
+}
+
+pub fn history_filter(dict: &Dictionary, wordlen:usize, trial:&Vec<String>, resp:&Vec<String>)->Result<WordFilter<MAX_ALPHABET>, String> {
+    let char_mapper = dict.get_char_mapper();
+
+    let mut translated_trial: Vec<Vec<u16>> = Vec::new();
+    let mut translated_resp: Vec<Vec<dict::WordleResp>> = Vec::new();
+    for (wordidx, word) in trial.iter().enumerate() {
+        let cur_word = char_mapper.try_map_word(word).ok_or(format!("invalid word {:?}", word))?;
+        if cur_word.len() == wordlen && resp[wordidx].len() == wordlen {
+            translated_trial.push(cur_word);
+            translated_resp.push(dict::parse_wordle_resp(&resp[wordidx]))
+        }
+    }
+
+    WordFilter::<MAX_ALPHABET>::from_wordle(wordlen as u16, &translated_trial, &translated_resp)
+}
+
+// per-request changes to which words are suggested; the default leaves the dictionary as is
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct PredictOptions {
+    #[serde(default)]
+    pub past_answers: PastAnswerMode,
+    // never suggest these
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    // suggest only these
+    pub include_only: Option<Vec<String>>,
+}
+
+pub const MAX_LISTED_WORDS: usize = 10000;
+
+impl PredictOptions {
+    pub fn is_default(&self) -> bool {
+        *self == PredictOptions::default()
+    }
+
+    pub fn validate(&self, dict: &Dictionary, wordlen: usize) -> Result<(), String> {
+        let listed: Vec<&String> = self.exclude.iter().chain(self.include_only.iter().flatten()).collect();
+        if listed.len() > MAX_LISTED_WORDS {
+            return Err(format!("exclude/include_only is too long"));
+        }
+        let char_mapper = dict.get_char_mapper();
+        for word in listed.into_iter() {
+            let mapped = match char_mapper.try_map_word(word.trim()) {
+                Some(mapped) if !mapped.is_empty() => mapped,
+                _ => return Err(format!("invalid word {:?}", word))
+            };
+            if mapped.len() != wordlen {
+                return Err(format!("{} is not a word of length {}", word, wordlen));
+            }
+        }
+        Ok(())
+    }
+
+    // applies exclude / include_only to a filtered view
+    pub fn restrict<'a>(&self, view: DictionaryView<'a>) -> DictionaryView<'a> {
+        if self.exclude.is_empty() && self.include_only.is_none() {
+            return view;
+        }
+        let char_mapper = view.char_mapper();
+        let excluded: HashSet<Vec<u16>> = self.exclude.iter().map(|word| char_mapper.map_word(&word.trim().to_string())).collect();
+        let included: Option<HashSet<Vec<u16>>> = self.include_only.as_ref()
+            .map(|words| words.iter().map(|word| char_mapper.map_word(&word.trim().to_string())).collect());
+        view.retain(|word| !excluded.contains(word) && included.as_ref().map_or(true, |included| included.contains(word)))
+    }
+
+    // `restrict` for the words that can still be the answer, which also drops excluded past answers
+    pub fn restrict_answers<'a>(&self, view: DictionaryView<'a>) -> DictionaryView<'a> {
+        let view = self.restrict(view);
+        if self.past_answers != PastAnswerMode::Exclude || view.dict.past_answer_set.is_empty() {
+            return view;
+        }
+        let past_answer_set = &view.dict.past_answer_set;
+        view.retain(|word| !past_answer_set.contains(word))
+    }
+}
+
+pub fn predict(dict: &Dictionary, word_filter: &WordFilter<MAX_ALPHABET>, wordlen:usize, k:usize, opts: &PredictOptions)->PredictionResult {
+    let char_mapper = dict.get_char_mapper();
+    let mut res_explore: Vec<(f32, String)> = Vec::new();
+    let mut res_exploit: Vec<(f32, String)> = Vec::new();
+
+    let exploit_dict = opts.restrict_answers(dict.apply_filter(word_filter, dict::FilterMode::ForExploit));
+    let explore_dict = opts.restrict(dict.apply_filter(word_filter, dict::FilterMode::ForExploration));
+
+    let freq = exploit_dict.log_letter_freq();
+    let is_char_explored = word_filter.is_char_explored();
+    let mut locfreq :Vec<Vec<f32>> = Vec::new();
+    for pos in 0..wordlen {
+        locfreq.push(exploit_dict.log_letter_locfreq(pos));
+    }
+
+    let tmp_explore = explore_dict.find_best_words_to_explore(k, &freq, &is_char_explored);
+    for (cur_score, cur_word) in tmp_explore.iter() {
+        res_explore.push((*cur_score, char_mapper.unmap_word(cur_word)))
+    }
+
+    let tmp_exploit = exploit_dict.find_best_words_to_exploit(k, locfreq, opts.past_answers);
+    for (cur_score, cur_word) in tmp_exploit.iter() {
+        res_exploit.push((*cur_score, char_mapper.unmap_word(cur_word)))
+    }
+
+    let candidates: Vec<&[u16]> = exploit_dict.words().collect();
+    let mut guesses: Vec<&Vec<u16>> = Vec::new();
+    let mut seen: HashSet<&Vec<u16>> = HashSet::new();
+    for (_, cur_word) in tmp_exploit.iter().chain(tmp_explore.iter()) {
+        if seen.insert(cur_word) {
+            guesses.push(cur_word);
+        }
+    }
+
+    let mut recommended = recommend(&char_mapper, &guesses, &candidates);
+    recommended.truncate(k);
+
+    PredictionResult {
+        to_exploit: res_exploit,
+        to_explore: res_explore,
+        candidate_cnt: candidates.len(),
+        recommended,
+        lookahead: None,
+    }
+}
+
+// a guess that may win right away is weighed against how far it narrows down the rest
+fn recommend(char_mapper: &Box<dyn dict::CharMapper>, guesses: &Vec<&Vec<u16>>, candidates: &Vec<&[u16]>) -> Vec<Recommendation> {
+    let mut res: Vec<Recommendation> = Vec::new();
+    for guess in guesses.iter() {
+        let counts = feedback_counts(guess, candidates);
+        res.push(Recommendation {
+            word: char_mapper.unmap_word(guess),
+            expected_guesses: expected_guesses(&counts, guess.len(), candidates.len()),
+            is_candidate: counts.iter().any(|(code, _)| is_solved(*code, guess.len())),
+        });
+    }
+    // on a tie, prefer the word that can win
+    res.sort_by(|a, b| a.expected_guesses.partial_cmp(&b.expected_guesses).unwrap().then(b.is_candidate.cmp(&a.is_candidate)));
+    res
+}
+
+// number of heuristic picks from each list the lookahead strategy simulates
+pub const LOOKAHEAD_WIDTH: usize = 10;
+// candidates tried as the reply within each feedback bucket, besides those picks
+pub const LOOKAHEAD_REPLY_WIDTH: usize = 16;
+pub const DEFAULT_TIME_BUDGET: Duration = Duration::from_millis(250);
+
+pub fn predict_with(dict: &Dictionary, word_filter: &WordFilter<MAX_ALPHABET>, wordlen:usize, k:usize, strategy: Strategy, time_budget: Duration, opts: &PredictOptions)->PredictionResult {
+    match strategy {
+        Strategy::Frequency => predict(dict, word_filter, wordlen, k, opts),
+        Strategy::Lookahead => lookahead(dict, word_filter, wordlen, k, time_budget, opts),
+    }
+}
+
+// a rough guess of the moves needed to pick one of `cnt` words; exact for one and two,
+// fitted to the decision tree of the english list above that
+fn estimate_remaining(cnt: usize) -> f32 {
+    match cnt {
+        0 | 1 => cnt as f32,
+        2 => 1.5,
+        _ => 1.0 + 0.8 * (cnt as f32).ln().powf(0.6)
+    }
+}
+
+// candidates by the response code they give to `guess`
+fn feedback_groups<'a>(guess: &[u16], candidates: &Vec<&'a [u16]>) -> BTreeMap<u64, Vec<&'a [u16]>> {
+    let mut groups: BTreeMap<u64, Vec<&'a [u16]>> = BTreeMap::new();
+    for answer in candidates.iter() {
+        groups.entry(dict::wordle_response_code(guess, answer)).or_insert_with(Vec::new).push(*answer);
+    }
+    groups
+}
+
+// (response code, number of candidates giving it), which is all the scores need
+fn feedback_counts(guess: &[u16], candidates: &Vec<&[u16]>) -> Vec<(u64, usize)> {
+    // up to 3^10 codes, a table is cheaper than sorting
+    if guess.len() <= 10 {
+        let mut table: Vec<u32> = vec![0; 3usize.pow(guess.len() as u32)];
+        for answer in candidates.iter() {
+            table[dict::wordle_response_code(guess, answer) as usize] += 1;
+        }
+        return table.into_iter().enumerate()
+            .filter(|(_, cnt)| *cnt > 0)
+            .map(|(code, cnt)| (code as u64, cnt as usize))
+            .collect();
+    }
+
+    let mut codes: Vec<u64> = candidates.iter().map(|answer| dict::wordle_response_code(guess, answer)).collect();
+    codes.sort_unstable();
+    let mut counts: Vec<(u64, usize)> = Vec::new();
+    for code in codes {
+        match counts.last_mut() {
+            Some((last, cnt)) if *last == code => *cnt += 1,
+            _ => counts.push((code, 1))
+        }
+    }
+    counts
+}
+
+fn group_counts(groups: &BTreeMap<u64, Vec<&[u16]>>) -> Vec<(u64, usize)> {
+    groups.iter().map(|(code, group)| (*code, group.len())).collect()
+}
+
+fn is_solved(code: u64, wordlen: usize) -> bool {
+    code == dict::solved_response_code(wordlen)
+}
+
+// guesses to solve when the guess splits `cnt` candidates as `counts` says, counting the guess
+fn expected_guesses(counts: &Vec<(u64, usize)>, wordlen: usize, cnt: usize) -> f32 {
+    let mut expected = 1.0;
+    for (code, size) in counts.iter() {
+        if !is_solved(*code, wordlen) {
+            expected += *size as f32 / cnt as f32 * estimate_remaining(*size);
+        }
+    }
+    expected
+}
+
+// bits of information the feedback carries on average
+fn feedback_entropy(counts: &Vec<(u64, usize)>, cnt: usize) -> f32 {
+    counts.iter().map(|(_, size)| {
+        let p = *size as f32 / cnt as f32;
+        -p * p.log2()
+    }).sum()
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct FeedbackBucket {
+    pub pattern: String,
+    pub size: usize,
+    pub probability: f32,
+    pub examples: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct GuessAnalysis {
+    pub word: String,
+    pub is_candidate: bool,
+    pub expected_guesses: f32,
+    pub entropy: f32,
+    // size of the largest bucket
+    pub worst_case: usize,
+    // largest first
+    pub buckets: Vec<FeedbackBucket>,
+}
+
+pub fn analyze_guess(char_mapper: &Box<dyn dict::CharMapper>, guess: &[u16], candidates: &Vec<&[u16]>, example_cnt: usize) -> GuessAnalysis {
+    let groups = feedback_groups(guess, candidates);
+    let counts = group_counts(&groups);
+    let mut buckets: Vec<FeedbackBucket> = groups.iter().map(|(code, group)| FeedbackBucket {
+        pattern: dict::format_response_code(*code, guess.len()),
+        size: group.len(),
+        probability: group.len() as f32 / candidates.len() as f32,
+        examples: group.iter().take(example_cnt).map(|word| char_mapper.unmap_word(&word.to_vec())).collect(),
+    }).collect();
+    buckets.sort_by(|a, b| b.size.cmp(&a.size).then(a.pattern.cmp(&b.pattern)));
+
+    GuessAnalysis {
+        word: char_mapper.unmap_word(&guess.to_vec()),
+        is_candidate: groups.keys().any(|code| is_solved(*code, guess.len())),
+        expected_guesses: expected_guesses(&counts, guess.len(), candidates.len()),
+        entropy: feedback_entropy(&counts, candidates.len()),
+        worst_case: buckets.first().map_or(0, |bucket| bucket.size),
+        buckets,
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct WhatIf {
+    pub candidate_cnt: usize,
+    pub guess: GuessAnalysis,
+    // the top recommendation for the same position
+    pub best: Option<GuessAnalysis>,
+    // how many more guesses the proposed word is expected to take than the best one
+    pub expected_loss: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct GuessScore {
+    pub word: String,
+    pub expected_guesses: f32,
+    pub entropy: f32,
+}
+
+fn score_guess(char_mapper: &Box<dyn dict::CharMapper>, guess: &[u16], candidates: &Vec<&[u16]>) -> GuessScore {
+    let counts = feedback_counts(guess, candidates);
+    GuessScore {
+        word: char_mapper.unmap_word(&guess.to_vec()),
+        expected_guesses: expected_guesses(&counts, guess.len(), candidates.len()),
+        entropy: feedback_entropy(&counts, candidates.len()),
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ReviewStep {
+    pub guess: GuessScore,
+    pub resp: String,
+    pub candidates_before: usize,
+    pub candidates_after: usize,
+    // bits actually gained, against the entropy the guess promised
+    pub info_bits: f32,
+    // the top recommendation at this point
+    pub best: Option<GuessScore>,
+    // info_bits - guess.entropy; positive when the feedback was better than expected
+    pub luck: f32,
+    // 100 when the guess was expected to do as well as the best one
+    pub skill: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Review {
+    pub answer: String,
+    pub solved: bool,
+    pub steps: Vec<ReviewStep>,
+    // averaged over the steps
+    pub skill: f32,
+    // summed over the steps
+    pub luck: f32,
+}
+
+pub fn review(dict: &Dictionary, wordlen: usize, trial: &Vec<String>, resp: &Vec<String>, answer: &String) -> Result<Review, String> {
+    let char_mapper = dict.get_char_mapper();
+    let mapped_answer = char_mapper.try_map_word(answer).ok_or(format!("invalid word {:?}", answer))?;
+    if mapped_answer.len() != wordlen {
+        return Err(format!("{} is not a word of length {}", answer, wordlen));
+    }
+    if !dict.view(wordlen).words().any(|word| word == mapped_answer.as_slice()) {
+        return Err(format!("{} is not in the word list", answer));
+    }
+
+    let mut steps: Vec<ReviewStep> = Vec::new();
+    let mut solved = false;
+    for (stepidx, word) in trial.iter().enumerate() {
+        let mapped_guess = char_mapper.try_map_word(word).ok_or(format!("invalid word {:?}", word))?;
+        if mapped_guess.len() != wordlen {
+            return Err(format!("{} is not a word of length {}", word, wordlen));
+        }
+        let pattern = dict::wordle_response(&mapped_guess, &mapped_answer);
+        if dict::format_wordle_resp(&pattern) != dict::format_wordle_resp(&dict::parse_wordle_resp(&resp[stepidx])) {
+            return Err(format!("resp {} to {} does not match the answer", resp[stepidx], word));
+        }
+
+        let before = history_filter(dict, wordlen, &trial[..stepidx].to_vec(), &resp[..stepidx].to_vec())?;
+        let after = history_filter(dict, wordlen, &trial[..=stepidx].to_vec(), &resp[..=stepidx].to_vec())?;
+        let exploit_dict = dict.apply_filter(&before, dict::FilterMode::ForExploit);
+        let candidates: Vec<&[u16]> = exploit_dict.words().collect();
+        let candidates_after = dict.apply_filter(&after, dict::FilterMode::ForExploit).len();
+
+        let guess = score_guess(&char_mapper, &mapped_guess, &candidates);
+        let best = predict(dict, &before, wordlen, 5, &PredictOptions::default()).recommended.first()
+            .map(|best| score_guess(&char_mapper, &char_mapper.map_word(&best.word), &candidates));
+        let info_bits = if candidates_after > 0 {
+            (candidates.len() as f32 / candidates_after as f32).log2()
+        } else {
+            0.0
+        };
+        let skill = match &best {
+            Some(best) => (100.0 * best.expected_guesses / guess.expected_guesses).min(100.0),
+            None => 100.0
+        };
+
+        steps.push(ReviewStep {
+            resp: dict::format_wordle_resp(&pattern),
+            candidates_before: candidates.len(),
+            candidates_after,
+            info_bits,
+            luck: info_bits - guess.entropy,
+            guess,
+            best,
+            skill,
+        });
+
+        if pattern.iter().all(|resp| *resp == dict::WordleResp::Green) {
+            solved = true;
+            break;
+        }
+    }
+
+    Ok(Review {
+        answer: char_mapper.unmap_word(&mapped_answer),
+        solved,
+        skill: if steps.is_empty() { 0.0 } else { steps.iter().map(|step| step.skill).sum::<f32>() / steps.len() as f32 },
+        luck: steps.iter().map(|step| step.luck).sum(),
+        steps,
+    })
+}
+
+// letter statistics of the words that can still be the answer, indexed like `alphabet`
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Heatmap {
+    pub candidate_cnt: usize,
+    pub alphabet: Vec<String>,
+    // positions[pos][i]: share of the candidates with alphabet[i] at pos
+    pub positions: Vec<Vec<f32>>,
+    // presence[i]: share of the candidates containing alphabet[i]
+    pub presence: Vec<f32>,
+}
+
+pub fn heatmap(dict: &Dictionary, wordlen: usize, trial: &Vec<String>, resp: &Vec<String>, opts: &PredictOptions) -> Result<Heatmap, String> {
+    opts.validate(dict, wordlen)?;
+    let word_filter = history_filter(dict, wordlen, trial, resp)?;
+    let exploit_dict = opts.restrict_answers(dict.apply_filter(&word_filter, dict::FilterMode::ForExploit));
+
+    let candidate_cnt = exploit_dict.len();
+    let share = |counts: Vec<usize>| -> Vec<f32> {
+        counts.into_iter().map(|cnt| if candidate_cnt > 0 { cnt as f32 / candidate_cnt as f32 } else { 0.0 }).collect()
+    };
+
+    Ok(Heatmap {
+        candidate_cnt,
+        alphabet: (1..=dict.char_mapper.alphabet_cnt()).map(|n| dict.char_mapper.unmap_symbol(n)).collect(),
+        positions: (0..wordlen).map(|pos| share(exploit_dict.letter_locfreq_counts(pos))).collect(),
+        presence: share(exploit_dict.letter_presence_counts()),
+    })
+}
+
+pub const WHATIF_EXAMPLES: usize = 5;
+
+pub fn what_if(dict: &Dictionary, wordlen: usize, trial: &Vec<String>, resp: &Vec<String>, guess: &String) -> Result<WhatIf, String> {
+    let char_mapper = dict.get_char_mapper();
+    let mapped_guess = char_mapper.try_map_word(guess).ok_or(format!("invalid word {:?}", guess))?;
+    if mapped_guess.len() != wordlen {
+        return Err(format!("{} is not a word of length {}", guess, wordlen));
+    }
+
+    let word_filter = history_filter(dict, wordlen, trial, resp)?;
+    let exploit_dict = dict.apply_filter(&word_filter, dict::FilterMode::ForExploit);
+    let candidates: Vec<&[u16]> = exploit_dict.words().collect();
+
+    let analysis = analyze_guess(&char_mapper, &mapped_guess, &candidates, WHATIF_EXAMPLES);
+    let best = predict(dict, &word_filter, wordlen, 5, &PredictOptions::default()).recommended.first()
+        .map(|best| analyze_guess(&char_mapper, &char_mapper.map_word(&best.word), &candidates, WHATIF_EXAMPLES));
+    let expected_loss = best.as_ref().map_or(0.0, |best| analysis.expected_guesses - best.expected_guesses);
+
+    Ok(WhatIf {
+        candidate_cnt: candidates.len(),
+        guess: analysis,
+        best,
+        expected_loss,
+    })
+}
+
+// expected guesses to finish `group` when `reply` is played next and the rest is estimated
+fn reply_cost(reply: &[u16], group: &Vec<&[u16]>) -> f32 {
+    expected_guesses(&feedback_counts(reply, group), reply.len(), group.len())
+}
+
+fn lookahead(dict: &Dictionary, word_filter: &WordFilter<MAX_ALPHABET>, wordlen:usize, k:usize, time_budget: Duration, opts: &PredictOptions)->PredictionResult {
+    let deadline = Instant::now() + time_budget;
+    let char_mapper = dict.get_char_mapper();
+    let mut res = predict(dict, word_filter, wordlen, k.max(LOOKAHEAD_WIDTH), opts);
+
+    let exploit_dict = opts.restrict_answers(dict.apply_filter(word_filter, dict::FilterMode::ForExploit));
+    let candidates: Vec<&[u16]> = exploit_dict.words().collect();
+
+    // k only sizes the result, the simulation stays LOOKAHEAD_WIDTH wide
+    let mut guesses: Vec<Vec<u16>> = Vec::new();
+    let picks = res.to_exploit.iter().take(LOOKAHEAD_WIDTH).chain(res.to_explore.iter().take(LOOKAHEAD_WIDTH));
+    for (_, word) in picks {
+        let mapped = char_mapper.map_word(word);
+        if !guesses.contains(&mapped) {
+            guesses.push(mapped);
+        }
+    }
+
+    let mut scores: Vec<LookaheadScore> = Vec::new();
+    'guesses: for guess in guesses.iter() {
+        let groups = feedback_groups(guess, &candidates);
+        let mut expected = 1.0;
+        for (code, group) in groups.iter() {
+            // the first guess is always scored so there is something to return
+            if !scores.is_empty() && Instant::now() >= deadline {
+                break 'guesses;
+            }
+            if is_solved(*code, guess.len()) {
+                continue;
+            }
+            let best_reply = group.iter().take(LOOKAHEAD_REPLY_WIDTH).cloned()
+                .chain(guesses.iter().map(|guess| guess.as_slice()))
+                .map(|reply| reply_cost(reply, group))
+                .fold(f32::INFINITY, f32::min);
+            expected += group.len() as f32 / candidates.len() as f32 * best_reply;
+        }
+        scores.push(LookaheadScore {
+            word: char_mapper.unmap_word(guess),
+            expected_guesses: expected,
+            bucket_cnt: groups.len(),
+        });
+    }
+    scores.sort_by(|a, b| a.expected_guesses.partial_cmp(&b.expected_guesses).unwrap());
+
+    res = res.truncate(k);
+    res.lookahead = Some(scores);
+    res
+}
+
+lazy_static! {
+    // lang -> (generation of the dictionary they were checked against, books)
+    static ref OPENING_BOOKS: RwLock<HashMap<String, (u64, Vec<Arc<OpeningBook>>)>> = RwLock::new(HashMap::new());
+}
+
+// predictions for the empty history and for every feedback to the strategy's first guess,
+// stored as `<lang>.<wordlen>.<strategy>.book.json` next to the word list
+#[derive(Serialize, Deserialize, Clone)]
+pub struct OpeningBook {
+    pub lang: String,
+    pub wordlen: usize,
+    pub strategy: Strategy,
+    pub k: usize,
+    // WordBucket::checksum of the words the book was built from
+    pub checksum: u64,
+    pub first_guess: String,
+    pub first: PredictionResult,
+    pub second: BTreeMap<String, PredictionResult>,
+}
+
+impl OpeningBook {
+    pub fn path(conf: &DictionaryConfig, wordlen: usize, strategy: Strategy) -> PathBuf {
+        Path::new(&conf.path).with_file_name(format!("{}.{}.{}.book.json", conf.lang, wordlen, strategy.name()))
+    }
+
+    pub fn find(conf: &DictionaryConfig) -> Result<Vec<OpeningBook>, String> {
+        let dir = match Path::new(&conf.path).parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from(".")
+        };
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) => return Err(format!("{}: {}", dir.display(), e))
+        };
+
+        let mut res: Vec<OpeningBook> = Vec::new();
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !name.starts_with(&format!("{}.", conf.lang)) || !name.ends_with(".book.json") {
+                continue;
+            }
+            // books only save time, so a broken one is skipped rather than failing the others
+            let book = fs::read_to_string(entry.path()).map_err(|e| e.to_string())
+                .and_then(|content| serde_json::from_str::<OpeningBook>(&content).map_err(|e| e.to_string()));
+            match book {
+                Ok(book) if book.lang == conf.lang => res.push(book),
+                Ok(_) => {}
+                Err(e) => eprintln!("ignoring opening book {}: {}", name, e)
+            }
+        }
+        Ok(res)
+    }
+
+    // books of the dictionary's current words, read on first use and again after a reload
+    pub fn get(dict: &Dictionary, wordlen: usize, strategy: Strategy) -> Option<Arc<OpeningBook>> {
+        let cached = match OPENING_BOOKS.read().unwrap().get(&dict.name) {
+            Some((generation, books)) if *generation == dict.generation => Some(books.clone()),
+            _ => None
+        };
+        let books = match cached {
+            Some(books) => books,
+            None => {
+                let books = OpeningBook::load(dict);
+                OPENING_BOOKS.write().unwrap().insert(dict.name.clone(), (dict.generation, books.clone()));
+                books
+            }
+        };
+        books.into_iter().find(|book| book.wordlen == wordlen && book.strategy == strategy)
+    }
+
+    fn load(dict: &Dictionary) -> Vec<Arc<OpeningBook>> {
+        let conf = match get_dict_service().read().unwrap().source(&dict.name) {
+            Some(conf) => conf.clone(),
+            None => return Vec::new()
+        };
+        let books = match OpeningBook::find(&conf) {
+            Ok(books) => books,
+            Err(e) => {
+                eprintln!("ignoring opening books of {}: {}", conf.lang, e);
+                return Vec::new();
+            }
+        };
+
+        let mut res: Vec<Arc<OpeningBook>> = Vec::new();
+        for book in books {
+            match dict.buckets.get(&book.wordlen) {
+                Some(bucket) if bucket.checksum() == book.checksum => res.push(Arc::new(book)),
+                _ => eprintln!("ignoring opening book {} {} {:?}: the word list has changed since it was built",
+                    conf.lang, book.wordlen, book.strategy)
+            }
+        }
+        res
+    }
+
+    pub fn build(dict: &Dictionary, wordlen: usize, strategy: Strategy, k: usize) -> Result<OpeningBook, String> {
+        let bucket = match dict.buckets.get(&wordlen) {
+            Some(bucket) => bucket,
+            None => return Err(format!("no words of length {}", wordlen))
+        };
+
+        let no_history = history_filter(dict, wordlen, &Vec::new(), &Vec::new())?;
+        let first = predict_with(dict, &no_history, wordlen, k, strategy, DEFAULT_TIME_BUDGET, &PredictOptions::default());
+        let first_guess = match strategy.pick(&first) {
+            Some(word) => word,
+            None => return Err("no guess to play".to_string())
+        };
+
+        let mapped_guess = dict.char_mapper.map_word(&first_guess);
+        let mut patterns: Vec<String> = bucket.iter()
+            .map(|answer| dict::format_wordle_resp(&dict::wordle_response(&mapped_guess, answer)))
+            .collect();
+        patterns.sort();
+        patterns.dedup();
+
+        let mut second: BTreeMap<String, PredictionResult> = BTreeMap::new();
+        for pattern in patterns.into_iter() {
+            let word_filter = history_filter(dict, wordlen, &vec![first_guess.clone()], &vec![pattern.clone()])?;
+            second.insert(pattern, predict_with(dict, &word_filter, wordlen, k, strategy, DEFAULT_TIME_BUDGET, &PredictOptions::default()));
+        }
+
+        Ok(OpeningBook {
+            lang: dict.name.clone(),
+            wordlen,
+            strategy,
+            k,
+            checksum: bucket.checksum(),
+            first_guess,
+            first,
+            second,
+        })
+    }
+
+    pub fn save<P>(&self, path: P) -> Result<(), String> where P: AsRef<Path> {
+        let content = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        fs::write(&path, content).map_err(|e| format!("{}: {}", path.as_ref().display(), e))
+    }
+
+    pub fn lookup(&self, dict: &Dictionary, trial: &Vec<String>, resp: &Vec<String>, k: usize) -> Option<PredictionResult> {
+        if k > self.k {
+            return None;
+        }
+
+        match trial.len() {
+            0 => Some(self.first.truncate(k)),
+            1 => {
+                let char_mapper = dict.get_char_mapper();
+                if char_mapper.map_word(&trial[0]) != char_mapper.map_word(&self.first_guess) {
+                    return None;
+                }
+                let pattern = dict::format_wordle_resp(&dict::parse_wordle_resp(&resp[0]));
+                self.second.get(&pattern).map(|res| res.truncate(k))
+            }
+            _ => None
+        }
+    }
+}
+
+// a guess chain deeper than this is treated as unsolved
+pub const MAX_TREE_DEPTH: usize = 20;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DecisionNode {
+    pub guess: String,
+    // words still possible when the guess is made
+    pub candidate_cnt: usize,
+    // whether the guess itself is one of them
+    pub is_answer: bool,
+    // the next move for every other feedback
+    pub next: BTreeMap<String, DecisionNode>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct TreeStats {
+    pub answer_cnt: usize,
+    // number of answers solved with exactly this many guesses
+    pub depths: BTreeMap<usize, usize>,
+    pub average_depth: f64,
+    pub max_depth: usize,
+    pub unsolved: usize,
+}
+
+// the moves a strategy makes against every word of one length
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DecisionTree {
+    pub lang: String,
+    pub wordlen: usize,
+    pub strategy: Strategy,
+    pub root: DecisionNode,
+    pub stats: TreeStats,
+}
+
+impl DecisionTree {
+    pub fn build(dict: &Dictionary, wordlen: usize, strategy: Strategy, first_guess: Option<String>) -> Result<DecisionTree, String> {
+        let bucket = match dict.buckets.get(&wordlen) {
+            Some(bucket) => bucket,
+            None => return Err(format!("no words of length {}", wordlen))
+        };
+        if let Some(word) = &first_guess {
+            if dict.char_mapper.map_word(word).len() != wordlen {
+                return Err(format!("{} is not a word of length {}", word, wordlen));
+            }
+        }
+
+        let mut stats = TreeStats::default();
+        let root = build_node(dict, wordlen, strategy, &mut Vec::new(), &mut Vec::new(), bucket.iter().collect(), first_guess, &mut stats)?;
+
+        stats.answer_cnt = bucket.len();
+        let solved: usize = stats.depths.values().sum();
+        if solved > 0 {
+            stats.average_depth = stats.depths.iter().map(|(depth, cnt)| (depth * cnt) as f64).sum::<f64>() / solved as f64;
+        }
+        stats.max_depth = stats.depths.keys().last().cloned().unwrap_or(0);
+
+        Ok(DecisionTree {
+            lang: dict.name.clone(),
+            wordlen,
+            strategy,
+            root,
+            stats,
+        })
+    }
+
+    // one line per answer: `guess feedback guess feedback ...`, ending in all greens
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        write_node_text(&self.root, &String::new(), &"2".repeat(self.wordlen), &mut out);
+        out
+    }
+}
+
+fn build_node(dict: &Dictionary, wordlen: usize, strategy: Strategy, trial: &mut Vec<String>, resp: &mut Vec<String>,
+    candidates: Vec<&[u16]>, guess: Option<String>, stats: &mut TreeStats) -> Result<DecisionNode, String> {
+    let char_mapper = dict.get_char_mapper();
+    let depth = trial.len() + 1;
+    let all_green = "2".repeat(wordlen);
+
+    let guess = match guess {
+        Some(guess) => Some(guess),
+        None => {
+            let word_filter = history_filter(dict, wordlen, trial, resp)?;
+            strategy.pick(&predict_with(dict, &word_filter, wordlen, 1, strategy, DEFAULT_TIME_BUDGET, &PredictOptions::default()))
+        }
+    };
+
+    let partition = |guess: &Vec<u16>| {
+        let mut groups: BTreeMap<String, Vec<&[u16]>> = BTreeMap::new();
+        for answer in candidates.iter() {
+            let pattern = dict::format_wordle_resp(&dict::wordle_response(guess, answer));
+            groups.entry(pattern).or_insert_with(Vec::new).push(*answer);
+        }
+        groups
+    };
+
+    // a guess that tells the candidates apart is needed to make progress;
+    // otherwise play one of the candidates
+    let mut mapped_guess = guess.map(|word| char_mapper.map_word(&word)).unwrap_or_default();
+    let mut groups = if mapped_guess.len() == wordlen { partition(&mapped_guess) } else { BTreeMap::new() };
+    if groups.len() < 2 && !groups.contains_key(&all_green) {
+        mapped_guess = candidates[0].to_vec();
+        groups = partition(&mapped_guess);
+    }
+
+    let guess = char_mapper.unmap_word(&mapped_guess);
+    let mut node = DecisionNode {
+        guess: guess.clone(),
+        candidate_cnt: candidates.len(),
+        is_answer: false,
+        next: BTreeMap::new(),
+    };
+    for (pattern, group) in groups.into_iter() {
+        if pattern == all_green {
+            node.is_answer = true;
+            *stats.depths.entry(depth).or_insert(0) += 1;
+        } else if depth >= MAX_TREE_DEPTH {
+            stats.unsolved += group.len();
+        } else {
+            trial.push(guess.clone());
+            resp.push(pattern.clone());
+            let child = build_node(dict, wordlen, strategy, trial, resp, group, None, stats)?;
+            trial.pop();
+            resp.pop();
+            node.next.insert(pattern, child);
+        }
+    }
+    Ok(node)
+}
+
+fn write_node_text(node: &DecisionNode, prefix: &String, all_green: &String, out: &mut String) {
+    if node.is_answer {
+        out.push_str(&format!("{}{} {}\n", prefix, node.guess, all_green));
+    }
+    for (pattern, child) in node.next.iter() {
+        write_node_text(child, &format!("{}{} {} ", prefix, node.guess, pattern), all_green, out);
+    }
+}
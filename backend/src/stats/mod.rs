@@ -0,0 +1,145 @@
+use std::{collections::BTreeMap, fs::{self, OpenOptions}, io::Write, path::Path, sync::Mutex, time::{SystemTime, UNIX_EPOCH}};
+use rocket::State;
+use rocket_contrib::json::Json;
+
+use crate::{Resp, respond};
+use crate::game::GameState;
+
+// a finished game, one json object per line in the stats file
+#[derive(Serialize, Deserialize, Clone)]
+pub struct GameRecord {
+    pub player: String,
+    pub game_id: String,
+    pub lang: String,
+    pub wordlen: usize,
+    pub puzzle: Option<String>,
+    pub hard_mode: bool,
+    pub answer: String,
+    pub guesses: Vec<String>,
+    pub won: bool,
+    // unix time in seconds
+    pub finished_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct PlayerStats {
+    pub player: String,
+    pub played: usize,
+    pub won: usize,
+    pub win_rate: f32,
+    pub current_streak: usize,
+    pub max_streak: usize,
+    // number of games won with this many guesses
+    pub histogram: BTreeMap<usize, usize>,
+}
+
+pub fn is_valid_player(player: &String) -> bool {
+    !player.is_empty() && player.len() <= 64
+        && player.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+pub struct StatsStore {
+    path: String,
+    records: Mutex<Vec<GameRecord>>,
+}
+
+impl StatsStore {
+    pub fn open(path: &String) -> Result<StatsStore, String> {
+        let mut records: Vec<GameRecord> = Vec::new();
+        if Path::new(path).exists() {
+            let content = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+            for (lineidx, line) in content.lines().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                // e.g. a line cut short by a crash; losing one game beats refusing to start
+                match serde_json::from_str::<GameRecord>(line) {
+                    Ok(record) => records.push(record),
+                    Err(e) => eprintln!("skipping {}:{}: {}", path, lineidx + 1, e)
+                }
+            }
+            // so that the next record does not end up on a truncated last line
+            if !content.is_empty() && !content.ends_with('\n') {
+                let mut file = OpenOptions::new().append(true).open(path).map_err(|e| format!("{}: {}", path, e))?;
+                writeln!(file).map_err(|e| format!("{}: {}", path, e))?;
+            }
+        }
+        Ok(StatsStore {
+            path: path.clone(),
+            records: Mutex::new(records),
+        })
+    }
+
+    // appends to the file first, so what is served is always on disk
+    pub fn record(&self, player: &String, game: &GameState) -> Result<(), String> {
+        let record = GameRecord {
+            player: player.clone(),
+            game_id: game.id.clone(),
+            lang: game.lang.clone(),
+            wordlen: game.wordlen,
+            puzzle: game.puzzle.clone(),
+            hard_mode: game.hard_mode,
+            answer: game.answer.clone().unwrap_or_default(),
+            guesses: game.guesses.clone(),
+            won: game.won,
+            finished_at: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()),
+        };
+        let line = serde_json::to_string(&record).map_err(|e| e.to_string())?;
+
+        let mut records = self.records.lock().unwrap();
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)
+            .map_err(|e| format!("{}: {}", self.path, e))?;
+        writeln!(file, "{}", line).map_err(|e| format!("{}: {}", self.path, e))?;
+        records.push(record);
+        Ok(())
+    }
+
+    // newest first
+    pub fn history(&self, player: &String, limit: usize) -> Vec<GameRecord> {
+        self.records.lock().unwrap().iter().rev()
+            .filter(|record| &record.player == player)
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    pub fn stats(&self, player: &String) -> PlayerStats {
+        let mut res = PlayerStats {
+            player: player.clone(),
+            ..PlayerStats::default()
+        };
+        for record in self.records.lock().unwrap().iter().filter(|record| &record.player == player) {
+            res.played += 1;
+            if record.won {
+                res.won += 1;
+                res.current_streak += 1;
+                res.max_streak = res.max_streak.max(res.current_streak);
+                *res.histogram.entry(record.guesses.len()).or_insert(0) += 1;
+            } else {
+                res.current_streak = 0;
+            }
+        }
+        if res.played > 0 {
+            res.win_rate = res.won as f32 / res.played as f32;
+        }
+        res
+    }
+}
+
+pub const DEFAULT_HISTORY_LIMIT: usize = 50;
+
+#[get("/stats/<player>")]
+pub fn stats(store: State<StatsStore>, player: String) -> Json<Resp<PlayerStats>> {
+    if !is_valid_player(&player) {
+        return respond(Err(format!("invalid player id {}", player)));
+    }
+    respond(Ok(store.stats(&player)))
+}
+
+#[get("/stats/<player>/history?<limit>")]
+pub fn history(store: State<StatsStore>, player: String, limit: Option<usize>) -> Json<Resp<Vec<GameRecord>>> {
+    if !is_valid_player(&player) {
+        return respond(Err(format!("invalid player id {}", player)));
+    }
+    respond(Ok(store.history(&player, limit.unwrap_or(DEFAULT_HISTORY_LIMIT))))
+}
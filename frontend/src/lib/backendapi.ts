@@ -7,9 +7,17 @@ export type RespSuggestion = {
   result?: {
     to_exploit: [number, string][],
     to_explore: [number, string][],
+    candidate_cnt: number,
+    recommended: Recommendation[],
   }
 }
 
+export type Recommendation = {
+  word: string,
+  expected_guesses: number,
+  is_candidate: boolean,
+}
+
 export async function getSuggestion(wordlen: number, dict:string, guess:string[], statuses:CharStatus[][], k:number): Promise<RespSuggestion> {
   const resp = await fetch(
     `/api/pred`,
@@ -40,3 +48,64 @@ export async function getSuggestion(wordlen: number, dict:string, guess:string[]
   const json = await resp.json();
   return json;
 }
+
+export type DictionaryInfo = {
+  lang: string,
+  charset: string,
+  alphabet_cnt: number,
+  word_cnt: number,
+  word_cnt_per_len: { [wordlen: string]: number },
+  answer_cnt: number,
+  past_answer_cnt: number,
+  loaded_at: number,
+  metadata: { [key: string]: string },
+}
+
+export type RespDictionaries = {
+  success: boolean,
+  msg?: string,
+  result?: DictionaryInfo[],
+}
+
+export async function getDictionaries(): Promise<RespDictionaries> {
+  const resp = await fetch(`/api/dict`);
+  const json = await resp.json();
+  return json;
+}
+
+export type Heatmap = {
+  candidate_cnt: number,
+  alphabet: string[],
+  positions: number[][],
+  presence: number[],
+}
+
+export type RespHeatmap = {
+  success: boolean,
+  msg?: string,
+  result?: Heatmap,
+}
+
+export async function getHeatmap(wordlen: number, dict:string, guess:string[], statuses:CharStatus[][]): Promise<RespHeatmap> {
+  const resp = await fetch(
+    `/api/heatmap`,
+    {
+      method: 'POST',
+      headers: {
+        'Content-Type': 'application/json',
+      },
+      body: JSON.stringify({
+        wordlen,
+        lang: dict,
+        trial: guess,
+        resp: statuses.map(e=>e.map(v=>{
+          if (v === 'present') return '1';
+          if (v === 'correct') return '2';
+          return '0';
+        }).join('')),
+      })
+    }
+  );
+  const json = await resp.json();
+  return json;
+}